@@ -0,0 +1,37 @@
+use std::{fs, path::Path};
+
+const CONTENT: [(&str, &str); 3] = [
+    ("cards", ".card.yaml"),
+    ("enemies", ".enemy.yaml"),
+    ("maps", ".map.yaml"),
+];
+
+fn main() {
+    let assets = Path::new("assets");
+    let mut index = String::from("({\n");
+    for (key, suffix) in CONTENT.iter() {
+        let folder = assets.join(key);
+        println!("cargo:rerun-if-changed={}", folder.display());
+        let mut paths = fs::read_dir(&folder)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| name.ends_with(suffix))
+                    .map(|name| format!("\"{key}/{name}\""))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        index.push_str(&format!(
+            "    \"{key}\": Files ( paths: [{}]),\n",
+            paths.join(", ")
+        ));
+    }
+    index.push_str("})\n");
+
+    let path = assets.join("content_index.assets");
+    if fs::read_to_string(&path).ok().as_deref() != Some(index.as_str()) {
+        fs::write(&path, index).expect("Couldn't write content index");
+    }
+}
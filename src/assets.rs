@@ -1,8 +1,13 @@
-use bevy::prelude::*;
+use bevy::{asset::Asset, prelude::*};
 use bevy_asset_loader::prelude::*;
 use bevy_generative_grammars::tracery::TraceryGrammar;
 
-use crate::card::Card;
+use crate::{card::Card, enemy::EnemyDefinition, map::MapDefinition};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub const CONTENT_ASSETS: &str = "content.assets";
+#[cfg(target_arch = "wasm32")]
+pub const CONTENT_ASSETS: &str = "content_index.assets";
 
 #[derive(AssetCollection, Resource)]
 pub struct Assets {
@@ -12,4 +17,22 @@ pub struct Assets {
     pub story: Handle<TraceryGrammar>,
     #[asset(key = "cards", collection(typed))]
     pub cards: Vec<Handle<Card>>,
+    #[asset(key = "enemies", collection(typed))]
+    pub enemies: Vec<Handle<EnemyDefinition>>,
+    #[asset(key = "maps", collection(typed))]
+    pub maps: Vec<Handle<MapDefinition>>,
+}
+
+pub fn sort_collections(mut assets: ResMut<Assets>, asset_server: Res<AssetServer>) {
+    sort_by_path(&mut assets.cards, &asset_server);
+    sort_by_path(&mut assets.enemies, &asset_server);
+    sort_by_path(&mut assets.maps, &asset_server);
+}
+
+fn sort_by_path<T: Asset>(handles: &mut [Handle<T>], asset_server: &AssetServer) {
+    handles.sort_by_key(|handle| {
+        asset_server
+            .get_handle_path(handle)
+            .map(|path| path.path().to_path_buf())
+    });
 }
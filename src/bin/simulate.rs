@@ -0,0 +1,219 @@
+use std::{env, fs, path::Path, process::ExitCode};
+
+use bevy::utils::HashMap;
+use mini_jam_intermission::{
+    card::Card,
+    enemy::{AiProfile, EnemyDefinition},
+    map::MapDefinition,
+    scene::scenario::{
+        scenario_map::generators::MapStyle,
+        simulation::{Battle, BattleOutcome},
+        ActorResource,
+    },
+    story::{Goal, GoalType, Scenario, ScenarioState},
+};
+
+struct Options {
+    battles: usize,
+    seed: u64,
+    goals: usize,
+    max_turns: usize,
+    tier: usize,
+    cards: String,
+    enemies: String,
+    maps: String,
+    map: Option<String>,
+    ai: Option<AiProfile>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            battles: 1000,
+            seed: 0,
+            goals: 2,
+            max_turns: 200,
+            tier: 0,
+            cards: "assets/cards".to_string(),
+            enemies: "assets/enemies".to_string(),
+            maps: "assets/maps".to_string(),
+            map: None,
+            ai: None,
+        }
+    }
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {arg}"))?;
+        match arg.as_str() {
+            "--battles" => options.battles = value.parse().map_err(|e| format!("{arg}: {e}"))?,
+            "--seed" => options.seed = value.parse().map_err(|e| format!("{arg}: {e}"))?,
+            "--goals" => options.goals = value.parse().map_err(|e| format!("{arg}: {e}"))?,
+            "--max-turns" => {
+                options.max_turns = value.parse().map_err(|e| format!("{arg}: {e}"))?
+            }
+            "--tier" => options.tier = value.parse().map_err(|e| format!("{arg}: {e}"))?,
+            "--cards" => options.cards = value,
+            "--enemies" => options.enemies = value,
+            "--maps" => options.maps = value,
+            "--map" => options.map = Some(value),
+            "--ai" => {
+                options.ai = Some(match value.as_str() {
+                    "random" => AiProfile::Random,
+                    "planner" => AiProfile::Planner,
+                    "aggressive" => AiProfile::Aggressive,
+                    "cautious" => AiProfile::Cautious,
+                    "support" => AiProfile::Support,
+                    "guard" => AiProfile::Guard,
+                    _ => return Err(format!("{arg}: unknown ai {value}")),
+                })
+            }
+            _ => return Err(format!("Unknown argument {arg}")),
+        }
+    }
+    Ok(options)
+}
+
+fn load_assets<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    suffix: &str,
+) -> Result<Vec<T>, String> {
+    let mut assets = vec![];
+    let entries = fs::read_dir(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if !path.to_string_lossy().ends_with(suffix) {
+            continue;
+        }
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        assets.push(serde_yaml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?);
+    }
+    Ok(assets)
+}
+
+fn scenario(goals: usize, map: Option<String>) -> Scenario {
+    Scenario {
+        initial_description: "Simulated scenario".to_string(),
+        state: ScenarioState::InProgress(0),
+        goals: (0..goals)
+            .map(|i| Goal {
+                description: format!("Reach goal {i}"),
+                goal_type: GoalType::ReachLocation(format!("goal-{i}")),
+                ..Default::default()
+            })
+            .collect(),
+        map: MapStyle {
+            authored: map,
+            ..Default::default()
+        },
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!(
+                "Usage: simulate [--battles N] [--seed S] [--goals G] [--max-turns T] [--tier T] [--cards DIR] [--enemies DIR] [--maps DIR] [--map NAME] [--ai PROFILE]"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let cards = match load_assets::<Card>(Path::new(&options.cards), ".card.yaml") {
+        Ok(cards) => cards
+            .into_iter()
+            .map(|card| (card.id.clone(), card))
+            .collect::<HashMap<_, _>>(),
+        Err(e) => {
+            eprintln!("Couldn't load cards - {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut enemies =
+        match load_assets::<EnemyDefinition>(Path::new(&options.enemies), ".enemy.yaml") {
+            Ok(enemies) => enemies
+                .into_iter()
+                .map(|enemy| (enemy.id.clone(), enemy))
+                .collect::<HashMap<_, _>>(),
+            Err(e) => {
+                eprintln!("Couldn't load enemies - {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+    let maps = match load_assets::<MapDefinition>(Path::new(&options.maps), ".map.yaml") {
+        Ok(maps) => maps
+            .into_iter()
+            .map(|map| (map.id.clone(), map))
+            .collect::<HashMap<_, _>>(),
+        Err(e) => {
+            eprintln!("Couldn't load maps - {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(ai) = options.ai {
+        for enemy in enemies.values_mut() {
+            enemy.ai = ai;
+        }
+    }
+
+    let mut deck = cards
+        .values()
+        .filter(|card| card.tier == 0)
+        .map(|card| card.id.clone())
+        .collect::<Vec<_>>();
+    deck.sort();
+    deck.truncate(3);
+
+    let mut victories = 0;
+    let mut defeats = 0;
+    let mut timeouts = 0;
+    let mut total_turns = 0;
+
+    for i in 0..options.battles {
+        let seed = options.seed.wrapping_add(i as u64);
+        let player = ActorResource {
+            health: 5,
+            max_health: 5,
+            max_energy: 3,
+            ..Default::default()
+        }
+        .with_deck(deck.clone(), seed);
+        let mut battle = Battle::setup(
+            scenario(options.goals, options.map.clone()),
+            cards.clone(),
+            &enemies,
+            &maps,
+            options.tier,
+            player,
+            seed,
+        );
+        match battle.run(options.max_turns) {
+            BattleOutcome::Victory => victories += 1,
+            BattleOutcome::Defeat => defeats += 1,
+            BattleOutcome::Timeout => timeouts += 1,
+        }
+        total_turns += battle.turns_taken;
+    }
+
+    println!("Battles: {}", options.battles);
+    println!("Victories: {victories}");
+    println!("Defeats: {defeats}");
+    println!("Timeouts: {timeouts}");
+    if options.battles > 0 {
+        println!(
+            "Average turns: {:.1}",
+            total_turns as f32 / options.battles as f32
+        );
+    }
+    ExitCode::SUCCESS
+}
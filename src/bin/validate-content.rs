@@ -0,0 +1,477 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy_generative_grammars::{
+    generator::StatefulGenerator,
+    tracery::{StatefulStringGenerator, TraceryGrammar},
+};
+use mini_jam_intermission::{
+    card::{Card, CardAction, TargetShape},
+    map::MapDefinition,
+    scene::scenario::ScenarioMap,
+    story::{Scenario, StoryPhase},
+};
+use serde::Deserialize;
+
+const MANIFESTS: [&str; 2] = ["dynamic_assets.assets", "content.assets"];
+const PHASES: [StoryPhase; 4] = [
+    StoryPhase::Setup,
+    StoryPhase::Start,
+    StoryPhase::FinalConfrontation,
+    StoryPhase::Complete,
+];
+
+#[derive(Deserialize)]
+struct Grammar {
+    starting_point: String,
+    rules: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Default)]
+struct Report {
+    errors: Vec<String>,
+}
+
+impl Report {
+    fn error(&mut self, error: impl Into<String>) {
+        self.errors.push(error.into());
+    }
+}
+
+fn find_files(path: &Path, suffix: &str, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.is_dir() {
+            find_files(&path, suffix, files)?;
+        } else if path.to_string_lossy().ends_with(suffix) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(())
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn manifest_paths(value: &ron::Value, paths: &mut BTreeSet<String>) {
+    match value {
+        ron::Value::Map(map) => {
+            for (key, value) in map.iter() {
+                match (key, value) {
+                    (ron::Value::String(key), ron::Value::String(path)) if key == "path" => {
+                        paths.insert(path.clone());
+                    }
+                    _ => manifest_paths(value, paths),
+                }
+            }
+        }
+        ron::Value::Seq(values) => {
+            for value in values.iter() {
+                match value {
+                    ron::Value::String(path) => {
+                        paths.insert(path.clone());
+                    }
+                    _ => manifest_paths(value, paths),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn load_manifests(root: &Path, report: &mut Report) -> BTreeSet<String> {
+    let mut paths = BTreeSet::new();
+    for manifest in MANIFESTS.iter() {
+        let path = root.join(manifest);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                report.error(format!("{}: {e}", path.display()));
+                continue;
+            }
+        };
+        let mut listed = BTreeSet::new();
+        match ron::from_str::<ron::Value>(&text) {
+            Ok(value) => manifest_paths(&value, &mut listed),
+            Err(e) => report.error(format!("{manifest}: {e}")),
+        }
+        for path in listed.iter() {
+            if !root.join(path).exists() {
+                report.error(format!("{manifest}: {path} does not exist"));
+            }
+        }
+        paths.append(&mut listed);
+    }
+    paths
+}
+
+fn is_listed(manifest: &BTreeSet<String>, name: &str) -> bool {
+    manifest.iter().any(|path| {
+        path == name
+            || name
+                .strip_prefix(path.as_str())
+                .map(|rest| rest.starts_with('/'))
+                .unwrap_or(false)
+    })
+}
+
+fn load_cards(root: &Path, report: &mut Report) -> Vec<(String, Card)> {
+    let mut files = vec![];
+    if let Err(e) = find_files(root, ".card.yaml", &mut files) {
+        report.error(e);
+    }
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let name = relative(root, &path);
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) => {
+                    report.error(format!("{name}: {e}"));
+                    return None;
+                }
+            };
+            let value = match serde_yaml::from_str::<serde_yaml::Value>(&text) {
+                Ok(value) => value,
+                Err(e) => {
+                    report.error(format!("{name}: {e}"));
+                    return None;
+                }
+            };
+            // Check the actions one at a time, so a bad one is reported by its position
+            let actions = value
+                .get("actions")
+                .and_then(|actions| actions.as_sequence());
+            let mut valid = true;
+            for (i, action) in actions.into_iter().flatten().enumerate() {
+                if let Err(e) = serde_yaml::from_value::<CardAction>(action.clone()) {
+                    report.error(format!("{name}: action {} - {e}", i + 1));
+                    valid = false;
+                }
+            }
+            if !valid {
+                return None;
+            }
+            match serde_yaml::from_value::<Card>(value) {
+                Ok(card) => Some((name, card)),
+                Err(e) => {
+                    report.error(format!("{name}: {e}"));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn check_cards(cards: &[(String, Card)], manifest: &BTreeSet<String>, report: &mut Report) {
+    let mut ids: BTreeMap<&str, &str> = BTreeMap::new();
+    for (name, card) in cards.iter() {
+        if let Some(previous) = ids.insert(&card.id, name) {
+            report.error(format!(
+                "{name}: duplicate id {} - already defined in {previous}",
+                card.id
+            ));
+        }
+        if !is_listed(manifest, name) {
+            report.error(format!("{name}: not listed in any asset manifest"));
+        }
+        for action in card.actions.iter().flat_map(|action| action.flattened()) {
+            if let CardAction::Attack {
+                damage: _,
+                range: 0,
+                shape: TargetShape::Single | TargetShape::Multi { count: _ },
+                bonus: _,
+            } = action
+            {
+                report.error(format!("{name}: {} has a zero-range attack", card.id));
+            }
+        }
+    }
+
+    for (name, card) in cards.iter() {
+        if let Some(upgrade) = &card.upgrades_to {
+            if !ids.contains_key(upgrade.as_str()) {
+                report.error(format!("{name}: upgrades to unknown card {upgrade}"));
+            }
+        }
+    }
+
+    let tiers = cards
+        .iter()
+        .map(|(_, card)| card.tier)
+        .collect::<BTreeSet<_>>();
+    if !cards.is_empty() && !tiers.contains(&0) {
+        report.error("No tier 0 cards - the player has nothing to start with");
+    }
+    for tier in tiers.iter().filter(|tier| **tier > 0) {
+        if !tiers.contains(&(tier - 1)) {
+            report.error(format!(
+                "Tier {tier} is unreachable - there are no tier {} cards",
+                tier - 1
+            ));
+        }
+    }
+}
+
+fn check_maps(root: &Path, manifest: &BTreeSet<String>, report: &mut Report) {
+    let mut files = vec![];
+    if let Err(e) = find_files(root, ".map.yaml", &mut files) {
+        report.error(e);
+    }
+    for path in files {
+        let name = relative(root, &path);
+        if !is_listed(manifest, &name) {
+            report.error(format!("{name}: not listed in any asset manifest"));
+        }
+        let grid = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                serde_yaml::from_str::<MapDefinition>(&text).map_err(|e| e.to_string())
+            })
+            .and_then(|definition| definition.parse_grid());
+        let grid = match grid {
+            Ok(grid) => grid,
+            Err(e) => {
+                report.error(format!("{name}: {e}"));
+                continue;
+            }
+        };
+        let map = ScenarioMap {
+            width: grid.width,
+            height: grid.height,
+            tiles: grid.tiles,
+        };
+        for goal in map.unreachable_targets() {
+            report.error(format!(
+                "{name}: goal {} can't be reached from the start",
+                goal + 1
+            ));
+        }
+    }
+}
+
+fn strip_actions(mut symbol: &str) -> &str {
+    while let Some(rest) = symbol.strip_prefix('[') {
+        symbol = match rest.find(']') {
+            Some(end) => &rest[end + 1..],
+            None => "",
+        };
+    }
+    symbol.split('.').next().unwrap_or_default()
+}
+
+fn check_grammar(name: &str, grammar: &Grammar, report: &mut Report) {
+    let mut defined = grammar.rules.keys().cloned().collect::<BTreeSet<_>>();
+    let mut referenced = BTreeSet::new();
+
+    for expansion in grammar.rules.values().flatten() {
+        for action in expansion.split('[').skip(1) {
+            if let Some((symbol, _)) = action.split_once(':') {
+                defined.insert(symbol.to_string());
+            }
+        }
+        if expansion.matches('#').count() % 2 != 0 {
+            report.error(format!("{name}: unbalanced # in \"{expansion}\""));
+            continue;
+        }
+        for symbol in expansion.split('#').skip(1).step_by(2) {
+            let symbol = strip_actions(symbol);
+            if !symbol.is_empty() {
+                referenced.insert(symbol.to_string());
+            }
+        }
+    }
+
+    referenced.insert(grammar.starting_point.clone());
+    for phase in PHASES.iter() {
+        referenced.insert(phase.symbol().to_string());
+    }
+
+    for symbol in referenced.difference(&defined) {
+        report.error(format!(
+            "{name}: symbol {symbol} is referenced but never defined"
+        ));
+    }
+}
+
+// Runs the game's own generator over every phase, with `choice` picking the same
+// alternative index from every rule so each alternative gets exercised at least once
+fn check_scenarios(name: &str, grammar: &Grammar, tracery: &TraceryGrammar, report: &mut Report) {
+    let choices = grammar.rules.values().map(Vec::len).max().unwrap_or(1);
+    let mut errors = BTreeSet::new();
+    for phase in PHASES.iter() {
+        let key = phase.symbol().to_string();
+        for choice in 0..choices {
+            let mut generator = StatefulStringGenerator::clone_grammar(tracery);
+            let mut pick = |len| if len == 0 { 0 } else { choice % len };
+            let text = generator.generate_at(&key, &mut pick).unwrap_or_default();
+            if let Err(e) = Scenario::parse(&text) {
+                errors.insert(format!("{name}: {key} - {e}"));
+            }
+        }
+    }
+    for error in errors {
+        report.error(error);
+    }
+}
+
+fn check_stories(root: &Path, manifest: &BTreeSet<String>, report: &mut Report) {
+    let mut files = vec![];
+    if let Err(e) = find_files(root, ".trace.yaml", &mut files) {
+        report.error(e);
+    }
+    for path in files {
+        let name = relative(root, &path);
+        if !is_listed(manifest, &name) {
+            report.error(format!("{name}: not listed in any asset manifest"));
+        }
+        let grammars = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                let grammar = serde_yaml::from_str::<Grammar>(&text).map_err(|e| e.to_string())?;
+                let tracery =
+                    serde_yaml::from_str::<TraceryGrammar>(&text).map_err(|e| e.to_string())?;
+                Ok((grammar, tracery))
+            });
+        match grammars {
+            Ok((grammar, tracery)) => {
+                check_grammar(&name, &grammar, report);
+                check_scenarios(&name, &grammar, &tracery, report);
+            }
+            Err(e) => report.error(format!("{name}: {e}")),
+        }
+    }
+}
+
+// Runs every check over the assets in `root`, returning how many cards were checked
+fn validate(root: &Path, report: &mut Report) -> usize {
+    let manifest = load_manifests(root, report);
+    let cards = load_cards(root, report);
+    check_cards(&cards, &manifest, report);
+    check_maps(root, &manifest, report);
+    check_stories(root, &manifest, report);
+    cards.len()
+}
+
+fn main() -> ExitCode {
+    let mut root = PathBuf::from("assets");
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--assets", Some(value)) => root = PathBuf::from(value),
+            _ => {
+                eprintln!("Usage: validate-content [--assets DIR]");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut report = Report::default();
+    let cards = validate(&root, &mut report);
+
+    for error in report.errors.iter() {
+        eprintln!("error: {error}");
+    }
+    if report.errors.is_empty() {
+        println!("Checked {cards} cards - no problems found");
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("{} problems found", report.errors.len());
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(fixture: &str) -> Vec<String> {
+        let mut report = Report::default();
+        validate(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join(fixture),
+            &mut report,
+        );
+        report.errors
+    }
+
+    fn manifest_entries(path: &Path) -> BTreeMap<String, ron::Value> {
+        let text = fs::read_to_string(path).unwrap();
+        match ron::from_str::<ron::Value>(&text).unwrap() {
+            ron::Value::Map(map) => map
+                .iter()
+                .filter_map(|(key, value)| match key {
+                    ron::Value::String(key) => Some((key.clone(), value.clone())),
+                    _ => None,
+                })
+                .collect(),
+            _ => panic!("{} isn't a map of assets", path.display()),
+        }
+    }
+
+    #[test]
+    fn shipped_content_is_valid() {
+        assert_eq!(errors("assets"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn broken_content_is_reported() {
+        let errors = errors("tests/fixtures/invalid-content");
+        let expected = [
+            "cards/duplicate.card.yaml: duplicate id base - already defined in cards/base.card.yaml",
+            "cards/poke.card.yaml: poke has a zero-range attack",
+            "Tier 2 is unreachable - there are no tier 1 cards",
+            "cards/base.card.yaml: upgrades to unknown card base-plus",
+            "cards/broken.card.yaml: action 2 - unknown variant `Fly`",
+            "cards/unlisted.card.yaml: not listed in any asset manifest",
+            "content.assets: cards/missing.card.yaml does not exist",
+            "maps/sealed.map.yaml: goal 1 can't be reached from the start",
+            "story.trace.yaml: symbol villain is referenced but never defined",
+        ];
+        for error in expected {
+            assert!(
+                errors.iter().any(|e| e.starts_with(error)),
+                "missing \"{error}\" in {errors:#?}"
+            );
+        }
+        assert_eq!(errors.len(), expected.len(), "{errors:#?}");
+    }
+
+    // build.rs writes the index wasm loads instead of the folders, so both have to list the same files
+    #[test]
+    fn content_index_matches_the_content_folders() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let folders = manifest_entries(&root.join("content.assets"));
+        let index = manifest_entries(&root.join("content_index.assets"));
+        assert_eq!(
+            folders.keys().collect::<Vec<_>>(),
+            index.keys().collect::<Vec<_>>()
+        );
+
+        for (key, folder) in folders.iter() {
+            let mut folder_paths = BTreeSet::new();
+            manifest_paths(folder, &mut folder_paths);
+            let mut files = vec![];
+            for path in folder_paths.iter() {
+                find_files(&root.join(path), "", &mut files).unwrap();
+            }
+            let files = files
+                .iter()
+                .map(|path| relative(&root, path))
+                .collect::<BTreeSet<_>>();
+
+            let mut indexed = BTreeSet::new();
+            manifest_paths(&index[key], &mut indexed);
+            assert_eq!(indexed, files, "{key}");
+        }
+    }
+}
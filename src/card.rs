@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use crate::{
     assets::{self},
     game_state::AppState,
+    scene::scenario::status::{Status, StatusKind},
+    ui::{MainText, MenuButton},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, TypeUuid)]
@@ -14,50 +16,482 @@ pub struct Card {
     pub name: String,
     pub actions: Vec<CardAction>,
     pub tier: usize,
+    #[serde(default = "default_cost")]
+    pub cost: usize,
+    #[serde(default)]
+    pub exhaust: bool,
+    #[serde(default)]
+    pub upgrades_to: Option<String>,
+    #[serde(default)]
+    pub upgrade: Option<CardUpgrade>,
+}
+
+fn default_cost() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CardUpgrade {
+    #[serde(default)]
+    pub damage: usize,
+    #[serde(default)]
+    pub range: usize,
+    #[serde(default)]
+    pub actions: Vec<CardAction>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CardAction {
     Move(usize),
-    Attack { damage: usize, range: usize },
-    Stun { range: usize, duration: usize },
+    Attack {
+        damage: usize,
+        range: usize,
+        #[serde(default)]
+        shape: TargetShape,
+        #[serde(default)]
+        bonus: Vec<Bonus>,
+    },
+    Stun {
+        range: usize,
+        duration: usize,
+        #[serde(default)]
+        shape: TargetShape,
+    },
+    ApplyStatus {
+        range: usize,
+        status: Status,
+        #[serde(default)]
+        shape: TargetShape,
+    },
+    Heal {
+        amount: usize,
+        range: usize,
+        #[serde(default)]
+        shape: TargetShape,
+    },
+    Push {
+        distance: usize,
+        range: usize,
+    },
+    Pull {
+        distance: usize,
+        range: usize,
+    },
+    Teleport {
+        range: usize,
+    },
+    Draw(usize),
+    Block(usize),
+    Summon {
+        enemy: String,
+        range: usize,
+    },
+    If {
+        condition: Condition,
+        then: Vec<CardAction>,
+        #[serde(default)]
+        otherwise: Vec<CardAction>,
+    },
+    Choose(Vec<Vec<CardAction>>),
+    Repeat {
+        times: Count,
+        actions: Vec<CardAction>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Condition {
+    TargetHas(StatusKind),
+    CasterHas(StatusKind),
+    TargetHealthAtMost(usize),
+    CasterHealthAtMost(usize),
+    AtLeast(Count, usize),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Count {
+    Fixed(usize),
+    EnemiesWithin(usize),
+    AlliesWithin(usize),
+    CardsInHand,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bonus {
+    pub when: Condition,
+    pub damage: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetShape {
+    #[default]
+    Single,
+    Multi {
+        count: usize,
+    },
+    Burst {
+        radius: usize,
+    },
+    Line {
+        length: usize,
+    },
+    Cone {
+        length: usize,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub enum Targetable {
     Path { max_distance: usize },
     Creature { max_distance: usize },
+    Attackable { max_distance: usize },
+    Tile { max_distance: usize },
+    EmptyTile { max_distance: usize },
+    Caster,
 }
 
-impl Targetable {
+impl TargetShape {
     pub fn num_targets(&self) -> usize {
         match self {
-            Targetable::Path { max_distance: _ } => 1,
-            Self::Creature { max_distance: _ } => 1,
+            TargetShape::Multi { count } => (*count).max(1),
+            _ => 1,
+        }
+    }
+
+    pub fn is_area(&self) -> bool {
+        matches!(
+            self,
+            TargetShape::Burst { radius: _ }
+                | TargetShape::Line { length: _ }
+                | TargetShape::Cone { length: _ }
+        )
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            TargetShape::Single => String::new(),
+            TargetShape::Multi { count } => format!(" on up to {count} targets"),
+            TargetShape::Burst { radius } => format!(" in a burst of radius {radius}"),
+            TargetShape::Line { length } => format!(" in a line of {length} squares"),
+            TargetShape::Cone { length } => format!(" in a cone of {length} squares"),
+        }
+    }
+}
+
+impl Condition {
+    pub fn describe(&self) -> String {
+        match self {
+            Condition::TargetHas(kind) => format!("the target has {}", kind.name()),
+            Condition::CasterHas(kind) => format!("you have {}", kind.name()),
+            Condition::TargetHealthAtMost(health) => {
+                format!("the target has at most {health} health")
+            }
+            Condition::CasterHealthAtMost(health) => format!("you have at most {health} health"),
+            Condition::AtLeast(count, value) => {
+                format!("{} is at least {value}", count.describe())
+            }
+            Condition::Not(condition) => format!("not {}", condition.describe()),
+            Condition::All(conditions) => conditions
+                .iter()
+                .map(|c| c.describe())
+                .collect::<Vec<_>>()
+                .join(" and "),
+            Condition::Any(conditions) => conditions
+                .iter()
+                .map(|c| c.describe())
+                .collect::<Vec<_>>()
+                .join(" or "),
         }
     }
 }
 
+impl Count {
+    pub fn describe(&self) -> String {
+        match self {
+            Count::Fixed(count) => format!("{count}"),
+            Count::EnemiesWithin(range) => format!("the number of enemies within {range}"),
+            Count::AlliesWithin(range) => format!("the number of allies within {range}"),
+            Count::CardsInHand => "the number of cards in hand".to_string(),
+        }
+    }
+
+    fn describe_repeat(&self) -> String {
+        match self {
+            Count::Fixed(count) => format!("{count} times"),
+            Count::EnemiesWithin(range) => format!("for each enemy within {range}"),
+            Count::AlliesWithin(range) => format!("for each ally within {range}"),
+            Count::CardsInHand => "for each card in hand".to_string(),
+        }
+    }
+}
+
+pub fn describe_actions(actions: &[CardAction]) -> String {
+    if actions.is_empty() {
+        "do nothing".to_string()
+    } else {
+        actions
+            .iter()
+            .map(|a| a.describe())
+            .collect::<Vec<_>>()
+            .join(", then ")
+    }
+}
+
 impl CardAction {
     pub fn describe(&self) -> String {
         match self {
             CardAction::Move(d) => format!("Move {d} squares"),
-            CardAction::Attack { damage, range } => format!("Attack for {damage} at range {range}"),
-            CardAction::Stun { range, duration } => {
-                format!("Stun an enemy at {range} for {duration} turns")
+            CardAction::Attack {
+                damage,
+                range,
+                shape,
+                bonus,
+            } => format!(
+                "Attack for {damage} at range {range}{}{}",
+                shape.describe(),
+                bonus
+                    .iter()
+                    .map(|b| format!(", +{} if {}", b.damage, b.when.describe()))
+                    .collect::<String>()
+            ),
+            CardAction::Stun {
+                range,
+                duration,
+                shape,
+            } => format!(
+                "Stun an enemy at {range} for {duration} turns{}",
+                shape.describe()
+            ),
+            CardAction::ApplyStatus {
+                range,
+                status,
+                shape,
+            } => format!(
+                "Apply {} at range {range}{}",
+                status.describe(),
+                shape.describe()
+            ),
+            CardAction::Heal {
+                amount,
+                range,
+                shape,
+            } => format!("Heal {amount} at range {range}{}", shape.describe()),
+            CardAction::Push { distance, range } => {
+                format!("Push a creature at range {range} back {distance} squares")
+            }
+            CardAction::Pull { distance, range } => {
+                format!("Pull a creature at range {range} in {distance} squares")
+            }
+            CardAction::Teleport { range } => format!("Teleport up to {range} squares"),
+            CardAction::Draw(count) => format!("Draw {count} cards"),
+            CardAction::Block(amount) => format!("Block {amount} damage"),
+            CardAction::Summon { enemy, range } => format!("Summon a {enemy} at range {range}"),
+            CardAction::If {
+                condition,
+                then,
+                otherwise,
+            } if otherwise.is_empty() => {
+                format!("If {}: {}", condition.describe(), describe_actions(then))
             }
+            CardAction::If {
+                condition,
+                then,
+                otherwise,
+            } => format!(
+                "If {}: {}. Otherwise: {}",
+                condition.describe(),
+                describe_actions(then),
+                describe_actions(otherwise)
+            ),
+            CardAction::Choose(options) => format!(
+                "Choose one: {}",
+                options
+                    .iter()
+                    .map(|option| describe_actions(option))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+            CardAction::Repeat { times, actions } => format!(
+                "Repeat {}: {}",
+                times.describe_repeat(),
+                describe_actions(actions)
+            ),
         }
     }
 
+    fn apply_upgrade(&mut self, upgrade: &CardUpgrade) {
+        match self {
+            CardAction::Move(distance) => *distance += upgrade.range,
+            CardAction::Attack {
+                damage,
+                range,
+                shape: _,
+                bonus: _,
+            } => {
+                *damage += upgrade.damage;
+                *range += upgrade.range;
+            }
+            CardAction::Stun {
+                range,
+                duration: _,
+                shape: _,
+            }
+            | CardAction::ApplyStatus {
+                range,
+                status: _,
+                shape: _,
+            }
+            | CardAction::Heal {
+                amount: _,
+                range,
+                shape: _,
+            }
+            | CardAction::Push { distance: _, range }
+            | CardAction::Pull { distance: _, range }
+            | CardAction::Teleport { range }
+            | CardAction::Summon { enemy: _, range } => *range += upgrade.range,
+            CardAction::Draw(_) | CardAction::Block(_) => {}
+            CardAction::If {
+                condition: _,
+                then,
+                otherwise,
+            } => {
+                for action in then.iter_mut().chain(otherwise.iter_mut()) {
+                    action.apply_upgrade(upgrade);
+                }
+            }
+            CardAction::Choose(options) => {
+                for action in options.iter_mut().flatten() {
+                    action.apply_upgrade(upgrade);
+                }
+            }
+            CardAction::Repeat { times: _, actions } => {
+                for action in actions.iter_mut() {
+                    action.apply_upgrade(upgrade);
+                }
+            }
+        }
+    }
+
+    pub fn flattened(&self) -> Vec<&CardAction> {
+        let nested = match self {
+            CardAction::If {
+                condition: _,
+                then,
+                otherwise,
+            } => then.iter().chain(otherwise.iter()).collect::<Vec<_>>(),
+            CardAction::Choose(options) => options.iter().flatten().collect(),
+            CardAction::Repeat { times: _, actions } => actions.iter().collect(),
+            _ => return vec![self],
+        };
+        nested.into_iter().flat_map(|a| a.flattened()).collect()
+    }
+
+    pub fn shape(&self) -> TargetShape {
+        match self {
+            CardAction::Attack {
+                damage: _,
+                range: _,
+                shape,
+                bonus: _,
+            }
+            | CardAction::Stun {
+                range: _,
+                duration: _,
+                shape,
+            }
+            | CardAction::ApplyStatus {
+                range: _,
+                status: _,
+                shape,
+            }
+            | CardAction::Heal {
+                amount: _,
+                range: _,
+                shape,
+            } => *shape,
+            _ => TargetShape::Single,
+        }
+    }
+
+    pub fn num_targets(&self) -> usize {
+        self.shape().num_targets()
+    }
+
     pub fn target(&self) -> Targetable {
         match self {
             CardAction::Move(d) => Targetable::Path { max_distance: *d },
-            CardAction::Attack { damage: _, range } => Targetable::Creature {
+            CardAction::Attack {
+                damage: _,
+                range,
+                shape,
+                bonus: _,
+            } if shape.is_area() => Targetable::Tile {
+                max_distance: *range,
+            },
+            CardAction::Attack {
+                damage: _,
+                range,
+                shape: _,
+                bonus: _,
+            } => Targetable::Attackable {
+                max_distance: *range,
+            },
+            CardAction::Stun {
+                range,
+                duration: _,
+                shape,
+            }
+            | CardAction::ApplyStatus {
+                range,
+                status: _,
+                shape,
+            }
+            | CardAction::Heal {
+                amount: _,
+                range,
+                shape,
+            } if shape.is_area() => Targetable::Tile {
                 max_distance: *range,
             },
-            CardAction::Stun { range, duration: _ } => Targetable::Creature {
+            CardAction::Stun {
+                range,
+                duration: _,
+                shape: _,
+            }
+            | CardAction::ApplyStatus {
+                range,
+                status: _,
+                shape: _,
+            }
+            | CardAction::Heal {
+                amount: _,
+                range,
+                shape: _,
+            }
+            | CardAction::Push { distance: _, range }
+            | CardAction::Pull { distance: _, range } => Targetable::Creature {
                 max_distance: *range,
             },
+            CardAction::Teleport { range } | CardAction::Summon { enemy: _, range } => {
+                Targetable::EmptyTile {
+                    max_distance: *range,
+                }
+            }
+            CardAction::Draw(_) | CardAction::Block(_) => Targetable::Caster,
+            CardAction::If {
+                condition: _,
+                then: _,
+                otherwise: _,
+            }
+            | CardAction::Choose(_)
+            | CardAction::Repeat {
+                times: _,
+                actions: _,
+            } => Targetable::Caster,
         }
     }
 }
@@ -70,17 +504,18 @@ impl Plugin for CardPlugin {
             .add_event::<CardSelectedEvent>()
             .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(setup_cards))
             .add_system(hoverable)
+            .add_system(count_selected)
             .add_plugin(YamlAssetPlugin::<Card>::new(&["card.yaml"]));
     }
 }
 
 impl Card {
     pub fn title(&self) -> String {
-        format!("{} - {}", &self.tier, &self.name)
+        format!("{} - {} ({} energy)", &self.tier, &self.name, &self.cost)
     }
 
     pub fn description(&self) -> String {
-        if !self.actions.is_empty() {
+        let description = if !self.actions.is_empty() {
             self.actions
                 .iter()
                 .map(|c| c.describe())
@@ -88,8 +523,111 @@ impl Card {
                 .join("\n")
         } else {
             format!("Describing {}", self.name)
+        };
+        if self.exhaust {
+            format!("{description}\nExhaust")
+        } else {
+            description
         }
     }
+
+    pub fn upgrade_id(&self) -> Option<String> {
+        self.upgrades_to
+            .clone()
+            .or_else(|| self.upgrade.as_ref().map(|_| format!("{}+", self.id)))
+    }
+
+    fn generate_upgrade(&self) -> Option<Card> {
+        if self.upgrades_to.is_some() {
+            return None;
+        }
+        let upgrade = self.upgrade.as_ref()?;
+        let mut actions = self.actions.clone();
+        for action in actions.iter_mut() {
+            action.apply_upgrade(upgrade);
+        }
+        actions.extend(upgrade.actions.iter().cloned());
+        Some(Card {
+            id: format!("{}+", self.id),
+            name: format!("{}+", self.name),
+            actions,
+            upgrade: None,
+            ..self.clone()
+        })
+    }
+
+    pub fn upgrade_diff(&self, upgraded: &Card) -> String {
+        let before = self.description();
+        let after = upgraded.description();
+        let mut lines = vec![];
+        if self.cost != upgraded.cost {
+            lines.push(format!("Cost {} -> {}", self.cost, upgraded.cost));
+        }
+        for line in before
+            .lines()
+            .filter(|line| !after.lines().any(|l| l == *line))
+        {
+            lines.push(format!("- {line}"));
+        }
+        for line in after.lines() {
+            if before.lines().any(|l| l == line) {
+                lines.push(line.to_string());
+            } else {
+                lines.push(format!("+ {line}"));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+impl Cards {
+    pub fn is_upgrade(&self, id: &str) -> bool {
+        self.cards
+            .values()
+            .any(|card| card.upgrade_id().as_deref() == Some(id))
+    }
+
+    pub fn upgrade_options<'a, T: Iterator<Item = &'a String>>(
+        &self,
+        owned: T,
+    ) -> Vec<(Card, Card)> {
+        let mut owned = owned.collect::<Vec<_>>();
+        owned.sort();
+        owned.dedup();
+        owned
+            .into_iter()
+            .filter_map(|id| {
+                let base = self.cards.get(id)?;
+                let upgraded = self.cards.get(&base.upgrade_id()?)?;
+                Some((base.clone(), upgraded.clone()))
+            })
+            .collect()
+    }
+
+    // Upgrading replaces the base card, so a card is owned once anything along its upgrade path is
+    fn is_owned(&self, available: &AvailableCards, card: &Card) -> bool {
+        std::iter::successors(Some(card), |card| self.cards.get(&card.upgrade_id()?))
+            .take(self.cards.len())
+            .any(|card| available.cards.contains_key(&card.id))
+    }
+
+    pub fn next_unlocks(&self, available: &AvailableCards) -> Vec<Card> {
+        let remaining_cards = self
+            .cards
+            .values()
+            .filter(|card| !self.is_owned(available, card) && !self.is_upgrade(&card.id))
+            .collect::<Vec<_>>();
+        let min = remaining_cards
+            .iter()
+            .fold(usize::MAX, |v, card| v.min(card.tier));
+        let mut unlocks = remaining_cards
+            .into_iter()
+            .filter(|card| card.tier <= min)
+            .cloned()
+            .collect::<Vec<_>>();
+        unlocks.sort_by(|a, b| a.id.cmp(&b.id));
+        unlocks
+    }
 }
 
 #[derive(Debug, Resource, Default)]
@@ -103,31 +641,47 @@ pub struct AvailableCards {
     pub cards: HashMap<String, Card>,
 }
 
-fn setup_cards(mut commands: Commands, assets: Res<assets::Assets>, card_asset: Res<Assets<Card>>) {
+pub fn collect_cards(assets: &assets::Assets, card_asset: &Assets<Card>) -> Cards {
     let mut cards = HashMap::new();
-    let mut available_cards = HashMap::new();
     for card_handle in assets.cards.iter() {
         if let Some(card) = card_asset.get(card_handle) {
             cards.insert(card.id.clone(), card.clone());
-            if card.tier == 0 {
-                available_cards.insert(card.id.clone(), card.clone());
-            }
         }
     }
+    let upgrades = cards
+        .values()
+        .filter_map(|card| card.generate_upgrade())
+        .collect::<Vec<_>>();
+    for upgrade in upgrades {
+        cards.entry(upgrade.id.clone()).or_insert(upgrade);
+    }
+    let mut cards = Cards {
+        cards,
+        available_cards: HashMap::new(),
+    };
+    cards.available_cards = cards
+        .cards
+        .values()
+        .filter(|card| card.tier == 0 && !cards.is_upgrade(&card.id))
+        .map(|card| (card.id.clone(), card.clone()))
+        .collect();
+    cards
+}
+
+fn setup_cards(mut commands: Commands, assets: Res<assets::Assets>, card_asset: Res<Assets<Card>>) {
+    let cards = collect_cards(&assets, &card_asset);
 
-    bevy::log::info!("Cards: {:?}", cards);
+    bevy::log::info!("Cards: {:?}", cards.cards);
     commands.insert_resource(AvailableCards {
-        cards: available_cards.clone(),
-    });
-    commands.insert_resource(Cards {
-        cards,
-        available_cards,
+        cards: cards.available_cards.clone(),
     });
+    commands.insert_resource(cards);
 }
 
 #[derive(Debug, Clone, Component)]
 pub struct CardUI {
     pub card_id: String,
+    pub upgrades: Option<String>,
     pub title: String,
     pub description: String,
     pub selected: bool,
@@ -138,6 +692,7 @@ impl CardUI {
     pub fn card(card: &Card) -> Self {
         Self {
             card_id: card.id.clone(),
+            upgrades: None,
             title: card.title(),
             description: card.description(),
             selected: false,
@@ -145,6 +700,58 @@ impl CardUI {
         }
     }
 
+    pub fn upgrade(base: &Card, upgraded: &Card) -> Self {
+        Self {
+            card_id: upgraded.id.clone(),
+            upgrades: Some(base.id.clone()),
+            title: format!("Upgrade: {}", upgraded.title()),
+            description: base.upgrade_diff(upgraded),
+            selected: false,
+            selectable: false,
+        }
+    }
+
+    pub fn spawn_row<T: IntoIterator<Item = CardUI>>(
+        parent: &mut ChildBuilder,
+        assets: &assets::Assets,
+        cards: T,
+    ) -> Entity {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::Center,
+                    flex_wrap: FlexWrap::Wrap,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                for card in cards {
+                    card.spawn(parent, assets);
+                }
+            })
+            .id()
+    }
+
+    pub fn refresh(&mut self, cards: &Cards) -> bool {
+        let card = match cards.cards.get(&self.card_id) {
+            Some(card) => card,
+            None => return false,
+        };
+        let refreshed = match self
+            .upgrades
+            .as_ref()
+            .and_then(|base| cards.cards.get(base))
+        {
+            Some(base) => CardUI::upgrade(base, card),
+            None => CardUI::card(card),
+        };
+        self.title = refreshed.title;
+        self.description = refreshed.description;
+        true
+    }
+
     pub fn selectable(self) -> Self {
         Self {
             selectable: true,
@@ -247,3 +854,202 @@ fn hoverable(
         }
     }
 }
+
+// Counts the selectable cards picked on a screen and shows its button once enough are chosen
+#[derive(Component)]
+pub struct SelectedCounter {
+    selected: usize,
+    total: usize,
+    button: String,
+    text: String,
+}
+
+impl SelectedCounter {
+    pub fn new<T: Into<String>, R: Into<String>>(total: usize, button: R, text: T) -> Self {
+        Self {
+            selected: 0,
+            total,
+            button: button.into(),
+            text: text.into(),
+        }
+    }
+
+    pub fn spawn(self, parent: &mut ChildBuilder) -> Entity {
+        parent.spawn((NodeBundle::default(), self)).id()
+    }
+}
+
+fn count_selected(
+    mut events: EventReader<CardSelectedEvent>,
+    mut commands: Commands,
+    mut counters: Query<(Entity, &mut SelectedCounter)>,
+    assets: Res<assets::Assets>,
+) {
+    let mut added = 0;
+    let mut removed = 0;
+
+    for event in events.iter() {
+        if event.0 {
+            added += 1;
+        } else {
+            removed += 1;
+        }
+    }
+
+    for (entity, mut counter) in counters.iter_mut() {
+        counter.selected += added;
+        counter.selected = counter.selected.checked_sub(removed).unwrap_or_default();
+
+        let selected = counter.selected;
+        let total = counter.total;
+        let button = counter.button.clone();
+        let text = counter.text.clone();
+
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).add_children(|parent| {
+            MainText::new(format!("Selected {selected}/{total} Cards"))
+                .size(10.)
+                .spawn(parent, &assets);
+
+            if selected == total {
+                MenuButton::Primary.spawn(button, text, parent, &assets);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(yaml: &str) -> Card {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn cards(yamls: &[&str]) -> Cards {
+        let mut cards = yamls
+            .iter()
+            .map(|yaml| card(yaml))
+            .map(|card| (card.id.clone(), card))
+            .collect::<HashMap<_, _>>();
+        let upgrades = cards
+            .values()
+            .filter_map(|card| card.generate_upgrade())
+            .collect::<Vec<_>>();
+        for upgrade in upgrades {
+            cards.entry(upgrade.id.clone()).or_insert(upgrade);
+        }
+        Cards {
+            cards,
+            available_cards: HashMap::new(),
+        }
+    }
+
+    const STRIKE: &str = "
+id: strike
+name: Strike
+actions:
+  - !Move 2
+  - !Attack
+    damage: 2
+    range: 1
+tier: 0
+upgrade:
+  damage: 1
+  range: 1
+  actions:
+    - !Draw 1
+";
+
+    #[test]
+    fn generated_upgrades_add_to_the_base_card() {
+        let strike = card(STRIKE);
+        let upgraded = strike.generate_upgrade().unwrap();
+
+        assert_eq!(strike.upgrade_id().as_deref(), Some("strike+"));
+        assert_eq!(upgraded.id, "strike+");
+        assert_eq!(upgraded.name, "Strike+");
+        assert_eq!(upgraded.cost, strike.cost);
+        assert!(upgraded.upgrade.is_none());
+        assert!(upgraded.upgrade_id().is_none());
+        assert!(matches!(
+            upgraded.actions.as_slice(),
+            [
+                CardAction::Move(3),
+                CardAction::Attack {
+                    damage: 3,
+                    range: 2,
+                    ..
+                },
+                CardAction::Draw(1),
+            ]
+        ));
+    }
+
+    #[test]
+    fn authored_upgrades_are_not_generated() {
+        let stun = card(
+            "
+id: stun
+name: Stun
+actions:
+  - !Move 2
+tier: 0
+upgrades_to: stun-plus
+upgrade:
+  range: 1
+",
+        );
+        assert!(stun.generate_upgrade().is_none());
+        assert_eq!(stun.upgrade_id().as_deref(), Some("stun-plus"));
+    }
+
+    #[test]
+    fn upgrade_diff_marks_changed_lines() {
+        let strike = card(STRIKE);
+        let upgraded = Card {
+            cost: 0,
+            ..strike.generate_upgrade().unwrap()
+        };
+        let describe = |card: &Card, index: usize| card.actions[index].describe();
+
+        assert_eq!(
+            strike.upgrade_diff(&upgraded),
+            [
+                "Cost 1 -> 0".to_string(),
+                format!("- {}", describe(&strike, 0)),
+                format!("- {}", describe(&strike, 1)),
+                format!("+ {}", describe(&upgraded, 0)),
+                format!("+ {}", describe(&upgraded, 1)),
+                format!("+ {}", describe(&upgraded, 2)),
+            ]
+            .join("\n")
+        );
+
+        let unchanged = strike.upgrade_diff(&strike);
+        assert_eq!(unchanged, strike.description());
+    }
+
+    #[test]
+    fn upgraded_bases_are_never_offered_again() {
+        let cards = cards(&[
+            STRIKE,
+            "id: jab\nname: Jab\nactions:\n  - !Draw 1\ntier: 0",
+            "id: lance\nname: Lance\nactions:\n  - !Draw 2\ntier: 1",
+        ]);
+        let mut available = AvailableCards::default();
+        for id in ["strike+", "jab"] {
+            available
+                .cards
+                .insert(id.to_string(), cards.cards[id].clone());
+        }
+
+        let unlocks = cards
+            .next_unlocks(&available)
+            .into_iter()
+            .map(|card| card.id)
+            .collect::<Vec<_>>();
+        assert_eq!(unlocks, vec!["lance".to_string()]);
+        assert!(cards.upgrade_options(available.cards.keys()).is_empty());
+    }
+}
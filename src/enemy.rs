@@ -0,0 +1,92 @@
+use bevy::{prelude::*, reflect::TypeUuid, utils::HashMap};
+use bevy_common_assets::yaml::YamlAssetPlugin;
+use serde::{Deserialize, Serialize};
+
+use crate::{assets, game_state::AppState};
+
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid)]
+#[uuid = "0d6f0b8e-5c2a-4f0c-9d55-2f4c8e1f7a31"]
+pub struct EnemyDefinition {
+    pub id: String,
+    pub name: String,
+    pub health: usize,
+    pub deck: Vec<String>,
+    #[serde(default = "default_energy")]
+    pub energy: usize,
+    #[serde(default)]
+    pub ai: AiProfile,
+    pub tier: usize,
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default = "default_material")]
+    pub material: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AiProfile {
+    Random,
+    Planner,
+    Aggressive,
+    Cautious,
+    Support,
+    Guard,
+}
+
+impl Default for AiProfile {
+    fn default() -> Self {
+        Self::Planner
+    }
+}
+
+fn default_energy() -> usize {
+    1
+}
+
+fn default_model() -> String {
+    "Mesh6/Primitive0".to_string()
+}
+
+fn default_material() -> String {
+    "Material7".to_string()
+}
+
+impl EnemyDefinition {
+    pub fn model_path(&self) -> String {
+        format!("models.gltf#{}", self.model)
+    }
+
+    pub fn material_path(&self) -> String {
+        format!("models.gltf#{}", self.material)
+    }
+}
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Enemies>()
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(setup_enemies))
+            .add_plugin(YamlAssetPlugin::<EnemyDefinition>::new(&["enemy.yaml"]));
+    }
+}
+
+#[derive(Debug, Resource, Default)]
+pub struct Enemies {
+    pub enemies: HashMap<String, EnemyDefinition>,
+}
+
+fn setup_enemies(
+    mut commands: Commands,
+    assets: Res<assets::Assets>,
+    enemy_assets: Res<Assets<EnemyDefinition>>,
+) {
+    let mut enemies = HashMap::new();
+    for handle in assets.enemies.iter() {
+        if let Some(enemy) = enemy_assets.get(handle) {
+            enemies.insert(enemy.id.clone(), enemy.clone());
+        }
+    }
+
+    bevy::log::info!("Enemies: {:?}", enemies.keys());
+    commands.insert_resource(Enemies { enemies });
+}
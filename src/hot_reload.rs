@@ -0,0 +1,232 @@
+use std::{fs, path::Path};
+
+use bevy::{
+    asset::{HandleId, LoadState},
+    prelude::*,
+    utils::HashSet,
+};
+use bevy_generative_grammars::tracery::TraceryGrammar;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    assets,
+    card::{collect_cards, AvailableCards, Card, CardUI, Cards},
+    run_seed::{RngStream, RunSeed},
+    story::Story,
+};
+
+const TOAST_DURATION: f32 = 4.;
+
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ToastEvent>()
+            .add_system(reload_cards)
+            .add_system(reload_story)
+            .add_system(report_failures)
+            .add_system(refresh_card_ui)
+            .add_system(show_toast)
+            .add_system(expire_toast);
+    }
+}
+
+pub struct ToastEvent(pub String);
+
+#[derive(Component)]
+struct Toast(Timer);
+
+fn reload_cards(
+    mut events: EventReader<AssetEvent<Card>>,
+    mut toasts: EventWriter<ToastEvent>,
+    assets: Option<Res<assets::Assets>>,
+    card_asset: Res<Assets<Card>>,
+    available_cards: Option<ResMut<AvailableCards>>,
+    cards: Option<ResMut<Cards>>,
+) {
+    let (assets, mut available_cards, mut cards) = match (assets, available_cards, cards) {
+        (Some(assets), Some(available_cards), Some(cards)) => (assets, available_cards, cards),
+        _ => return,
+    };
+    let modified = events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => card_asset.get(handle),
+            _ => None,
+        })
+        .map(|card| card.id.clone())
+        .collect::<Vec<_>>();
+    if modified.is_empty() {
+        return;
+    }
+
+    *cards = collect_cards(&assets, &card_asset);
+    let available = available_cards
+        .cards
+        .keys()
+        .filter_map(|id| cards.cards.get(id))
+        .map(|card| (card.id.clone(), card.clone()))
+        .collect();
+    available_cards.cards = available;
+
+    info!("Reloaded cards {modified:?}");
+    toasts.send(ToastEvent(format!("Reloaded {}", modified.join(", "))));
+}
+
+fn reload_story(
+    mut events: EventReader<AssetEvent<TraceryGrammar>>,
+    mut toasts: EventWriter<ToastEvent>,
+    assets: Option<Res<assets::Assets>>,
+    grammars: Res<Assets<TraceryGrammar>>,
+    story: Option<ResMut<Story>>,
+    seed: Res<RunSeed>,
+) {
+    let assets = match assets {
+        Some(assets) => assets,
+        None => return,
+    };
+    let modified = events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => handle == &assets.story,
+        _ => false,
+    });
+    if !modified {
+        return;
+    }
+    let grammar = match grammars.get(&assets.story) {
+        Some(grammar) => grammar,
+        None => return,
+    };
+    let rng = &mut seed.stream(RngStream::Story);
+    let reloaded = match story {
+        Some(mut story) => story.reload(rng, grammar),
+        None => Story::validate(rng, grammar),
+    };
+    if let Err(e) = reloaded {
+        let message = format!("Couldn't reload story grammar - {e}");
+        warn!("{message}");
+        toasts.send(ToastEvent(message));
+        return;
+    }
+
+    info!("Reloaded story grammar");
+    toasts.send(ToastEvent("Reloaded story grammar".to_string()));
+}
+
+fn report_failures(
+    mut failed: Local<HashSet<HandleId>>,
+    mut toasts: EventWriter<ToastEvent>,
+    assets: Option<Res<assets::Assets>>,
+    asset_server: Res<AssetServer>,
+) {
+    let assets = match assets {
+        Some(assets) => assets,
+        None => return,
+    };
+    let cards = assets.cards.iter().map(|handle| (handle.id(), true));
+    for (handle, is_card) in cards.chain(std::iter::once((assets.story.id(), false))) {
+        if asset_server.get_load_state(handle) != LoadState::Failed {
+            failed.remove(&handle);
+            continue;
+        }
+        if !failed.insert(handle) {
+            continue;
+        }
+        let path = match asset_server.get_handle_path(handle) {
+            Some(path) => path.path().to_path_buf(),
+            None => continue,
+        };
+        let full_path = Path::new("assets").join(&path);
+        let reason = if is_card {
+            parse_error::<Card>(&full_path)
+        } else {
+            parse_error::<TraceryGrammar>(&full_path)
+        };
+        let message = match reason {
+            Some(reason) => format!("Couldn't reload {} - {reason}", path.display()),
+            None => format!("Couldn't reload {}", path.display()),
+        };
+        warn!("{message}");
+        toasts.send(ToastEvent(message));
+    }
+}
+
+fn parse_error<T: DeserializeOwned>(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_yaml::from_str::<T>(&text).map_err(|e| e.to_string()))
+        .err()
+}
+
+fn refresh_card_ui(
+    cards: Option<Res<Cards>>,
+    mut card_uis: Query<(&mut CardUI, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let cards = match cards {
+        Some(cards) if cards.is_changed() => cards,
+        _ => return,
+    };
+    for (mut card_ui, children) in card_uis.iter_mut() {
+        if !card_ui.refresh(&cards) {
+            continue;
+        }
+        let values = [card_ui.title.clone(), card_ui.description.clone()];
+        for (child, value) in children.iter().zip(values) {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if let Some(section) = text.sections.first_mut() {
+                    section.value = value;
+                }
+            }
+        }
+    }
+}
+
+fn show_toast(
+    mut commands: Commands,
+    mut events: EventReader<ToastEvent>,
+    assets: Option<Res<assets::Assets>>,
+) {
+    let assets = match assets {
+        Some(assets) => assets,
+        None => return,
+    };
+    for (i, event) in events.iter().enumerate() {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            right: Val::Px(10.),
+                            top: Val::Px(10. + 40. * i as f32),
+                            ..Default::default()
+                        },
+                        padding: UiRect::all(Val::Px(8.)),
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(Color::hex("00131b").unwrap_or_default()),
+                    z_index: ZIndex::Global(10),
+                    ..Default::default()
+                },
+                Toast(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    event.0.clone(),
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size: 14.,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ));
+            });
+    }
+}
+
+fn expire_toast(mut commands: Commands, mut toasts: Query<(Entity, &mut Toast)>, time: Res<Time>) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
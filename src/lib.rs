@@ -0,0 +1,20 @@
+// Bevy code commonly triggers these lints and they may be important signals
+// about code quality. They are sometimes hard to avoid though, and the CI
+// workflow treats them as errors, so this allows them throughout the project.
+// Feel free to delete this line.
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+pub mod assets;
+pub mod card;
+pub mod enemy;
+pub mod game_state;
+#[cfg(feature = "dev")]
+pub mod hot_reload;
+pub mod map;
+pub mod menu;
+pub mod overworld;
+pub mod run_seed;
+pub mod save;
+pub mod scene;
+pub mod story;
+pub mod ui;
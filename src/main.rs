@@ -1,18 +1,3 @@
-// Bevy code commonly triggers these lints and they may be important signals
-// about code quality. They are sometimes hard to avoid though, and the CI
-// workflow treats them as errors, so this allows them throughout the project.
-// Feel free to delete this line.
-#![allow(clippy::too_many_arguments, clippy::type_complexity)]
-
-mod assets;
-mod card;
-mod game_state;
-mod menu;
-mod overworld;
-mod scene;
-mod story;
-mod ui;
-
 use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
 use bevy_asset_loader::prelude::*;
 use bevy_egui::EguiPlugin;
@@ -22,32 +7,51 @@ use bevy_inspector_egui::*;
 use bevy_mod_picking::PickingCameraBundle;
 use bevy_sequential_actions::SequentialActionsPlugin;
 use bevy_turborand::RngPlugin;
+#[cfg(feature = "dev")]
+use mini_jam_intermission::hot_reload::HotReloadPlugin;
 
-use card::CardPlugin;
-use game_state::AppState;
-use menu::MenuPlugin;
-use overworld::OverworldPlugin;
-
-use scene::{board::board_assets::BoardAssets, ScenePlugin};
+use mini_jam_intermission::{
+    assets,
+    card::CardPlugin,
+    enemy::EnemyPlugin,
+    game_state::AppState,
+    map::MapPlugin,
+    menu::MenuPlugin,
+    overworld::OverworldPlugin,
+    run_seed::{RunRngs, RunSeed},
+    save::SavePlugin,
+    scene::{board::board_assets::BoardAssets, ScenePlugin},
+    ui::UIPlugin,
+};
 use smooth_bevy_cameras::{
     controllers::orbit::{OrbitCameraBundle, OrbitCameraController, OrbitCameraPlugin},
     LookTransformPlugin,
 };
-use ui::UIPlugin;
 
 fn main() {
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
+    let seed = RunSeed::from_args(std::env::args().skip(1)).unwrap_or_else(RunSeed::random);
+
     let mut app = App::new();
-    app.add_plugins(DefaultPlugins.set(WindowPlugin {
-        window: WindowDescriptor {
-            fit_canvas_to_parent: true,
-            ..Default::default()
-        },
-        ..Default::default()
-    }))
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                window: WindowDescriptor {
+                    fit_canvas_to_parent: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .set(AssetPlugin {
+                watch_for_changes: cfg!(feature = "dev"),
+                ..Default::default()
+            }),
+    )
     .insert_resource(ClearColor(Color::hex("25215e").unwrap_or_default()))
+    .insert_resource(seed)
+    .insert_resource(RunRngs::new(seed))
     .add_plugin(EguiPlugin)
     .add_plugin(LookTransformPlugin)
     .add_plugin(OrbitCameraPlugin::default())
@@ -57,28 +61,35 @@ fn main() {
     .add_plugin(TraceryAssetPlugin::new().with_yaml(&["trace.yaml"]));
 
     #[cfg(feature = "dev")]
-    app.add_plugin(WorldInspectorPlugin::new());
+    app.add_plugin(WorldInspectorPlugin::new())
+        .add_plugin(HotReloadPlugin);
 
     app.add_plugin(UIPlugin)
         .add_plugin(CardPlugin)
+        .add_plugin(EnemyPlugin)
+        .add_plugin(MapPlugin)
         .add_plugin(MenuPlugin)
         .add_plugin(OverworldPlugin)
         .add_plugin(ScenePlugin)
+        .add_plugin(SavePlugin)
         .add_state(AppState::Loading)
         .add_loading_state(
             LoadingState::new(AppState::Loading)
                 .continue_to_state(AppState::MainMenu)
                 .with_dynamic_collections::<StandardDynamicAssetCollection>(vec![
                     "dynamic_assets.assets",
+                    assets::CONTENT_ASSETS,
                 ])
                 .with_collection::<assets::Assets>()
                 .with_collection::<BoardAssets>(),
         )
+        .add_system_set(SystemSet::on_exit(AppState::Loading).with_system(assets::sort_collections))
         .add_startup_system(setup)
         .run();
 }
 
-fn setup(mut commands: Commands) {
+fn setup(mut commands: Commands, seed: Res<RunSeed>) {
+    info!("Run seed: {}", seed.0);
     let eye = Vec3::new(0., 15., 0.);
     let target = Vec3::default();
     commands
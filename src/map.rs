@@ -0,0 +1,125 @@
+use bevy::{prelude::*, reflect::TypeUuid, utils::HashMap};
+use bevy_common_assets::yaml::YamlAssetPlugin;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets,
+    game_state::AppState,
+    scene::scenario::scenario_map::{Tile, TileTag, TileType},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid)]
+#[uuid = "6a1c3f52-9e4d-4b0a-8f2e-71d5c0b9e6a4"]
+pub struct MapDefinition {
+    pub id: String,
+    pub grid: String,
+}
+
+pub struct AuthoredGrid {
+    pub width: usize,
+    pub height: usize,
+    pub tiles: Vec<Tile>,
+    pub enemy_spawns: Vec<(usize, usize)>,
+}
+
+impl MapDefinition {
+    pub fn parse_grid(&self) -> Result<AuthoredGrid, String> {
+        let rows = self
+            .grid
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>();
+        let height = rows.len();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        if width == 0 || height == 0 {
+            return Err(format!("Map {} has an empty grid", self.id));
+        }
+
+        let mut tiles = Vec::with_capacity(width * height);
+        let mut enemy_spawns = vec![];
+        for x in 0..width {
+            for (y, row) in rows.iter().enumerate() {
+                let symbol = row.chars().nth(x).unwrap_or(' ');
+                let (tile_type, tag) = match symbol {
+                    '.' => (TileType::Floor, TileTag::None),
+                    '#' => (TileType::Wall, TileTag::None),
+                    'o' => (TileType::Obstacle, TileTag::None),
+                    ',' => (TileType::Difficult, TileTag::None),
+                    '^' => (TileType::Hazard, TileTag::None),
+                    '+' => (TileType::Spring, TileTag::None),
+                    '*' => (TileType::Ice, TileTag::None),
+                    ' ' | '_' => (TileType::Empty, TileTag::None),
+                    'S' => (TileType::Floor, TileTag::Start),
+                    'E' => {
+                        enemy_spawns.push((x, y));
+                        (TileType::Floor, TileTag::None)
+                    }
+                    '1'..='9' => (
+                        TileType::Floor,
+                        TileTag::Target(symbol as usize - '1' as usize),
+                    ),
+                    _ => {
+                        return Err(format!(
+                            "Map {} has an unknown symbol '{symbol}' at ({x}, {y})",
+                            self.id
+                        ))
+                    }
+                };
+                tiles.push(Tile {
+                    pos: (x, y),
+                    tile_type,
+                    tag,
+                    ..Default::default()
+                });
+            }
+        }
+
+        if !tiles.iter().any(|tile| tile.tag == TileTag::Start) {
+            return Err(format!("Map {} has no start tile", self.id));
+        }
+
+        enemy_spawns.sort_by_key(|(x, y)| (*y, *x));
+
+        Ok(AuthoredGrid {
+            width,
+            height,
+            tiles,
+            enemy_spawns,
+        })
+    }
+}
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Maps>()
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(setup_maps))
+            .add_plugin(YamlAssetPlugin::<MapDefinition>::new(&["map.yaml"]));
+    }
+}
+
+#[derive(Debug, Resource, Default)]
+pub struct Maps {
+    pub maps: HashMap<String, MapDefinition>,
+}
+
+fn setup_maps(
+    mut commands: Commands,
+    assets: Res<assets::Assets>,
+    map_assets: Res<Assets<MapDefinition>>,
+) {
+    let mut maps = HashMap::new();
+    for handle in assets.maps.iter() {
+        if let Some(map) = map_assets.get(handle) {
+            maps.insert(map.id.clone(), map.clone());
+        }
+    }
+
+    bevy::log::info!("Maps: {:?}", maps.keys());
+    commands.insert_resource(Maps { maps });
+}
@@ -1,29 +1,130 @@
-use crate::{assets, game_state::AppState, ui::*};
+use crate::{
+    assets,
+    card::Cards,
+    game_state::AppState,
+    run_seed::{RunRngs, RunSeed},
+    save::RunSnapshot,
+    ui::*,
+};
 use bevy::prelude::*;
+use bevy_generative_grammars::tracery::TraceryGrammar;
 
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(display_menu))
-            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(check_click))
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(check_click)
+                    .with_system(edit_seed),
+            )
             .add_system_set(clear_ui_system_set(AppState::MainMenu));
     }
 }
 
-fn display_menu(mut commands: Commands, assets: Res<assets::Assets>) {
+// The digits typed so far - empty until the player starts typing, and only parsed on Start
+#[derive(Component, Default)]
+struct SeedInput(Option<String>);
+
+fn display_menu(mut commands: Commands, assets: Res<assets::Assets>, seed: Res<RunSeed>) {
     UiRoot::spawn(&mut commands, |parent| {
         MainText::new("Intermission")
             .size(100.)
             .spawn(parent, &assets);
+        parent.spawn((
+            TextBundle::from_section(
+                seed_text(&seed.0.to_string()),
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 20.,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            )
+            .with_style(Style {
+                margin: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            }),
+            SeedInput::default(),
+        ));
         MenuButton::Primary.spawn("start", "Start", parent, &assets);
+        if RunSnapshot::load().is_some() {
+            MenuButton::Primary.spawn("continue", "Continue", parent, &assets);
+        }
     });
 }
-fn check_click(mut app_state: ResMut<State<AppState>>, mut clicked: EventReader<ButtonClickEvent>) {
+
+fn seed_text(seed: &str) -> String {
+    format!("Seed: {seed} (type to change)")
+}
+
+fn edit_seed(
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut input: Query<(&mut SeedInput, &mut Text)>,
+) {
+    let typed = characters
+        .iter()
+        .filter(|character| character.char.is_ascii_digit())
+        .map(|character| character.char)
+        .collect::<String>();
+    let back = keys.just_pressed(KeyCode::Back);
+    let delete = keys.just_pressed(KeyCode::Delete);
+    if typed.is_empty() && !back && !delete {
+        return;
+    }
+
+    for (mut input, mut text) in input.iter_mut() {
+        // The first key replaces the shown seed rather than adding digits to it
+        let value = input.0.get_or_insert_with(String::new);
+        value.push_str(&typed);
+        if back {
+            value.pop();
+        }
+        if delete {
+            value.clear();
+        }
+        if let Some(section) = text.sections.first_mut() {
+            section.value = seed_text(value);
+        }
+    }
+}
+
+fn check_click(
+    mut commands: Commands,
+    mut app_state: ResMut<State<AppState>>,
+    mut clicked: EventReader<ButtonClickEvent>,
+    mut seed: ResMut<RunSeed>,
+    mut input: Query<(&SeedInput, &mut Text)>,
+    assets: Res<assets::Assets>,
+    stories: Res<Assets<TraceryGrammar>>,
+    cards: Res<Cards>,
+) {
     for click in clicked.iter() {
         let ButtonClickEvent(val, _) = click;
         if val == "start" {
+            if let Ok((SeedInput(Some(typed)), mut text)) = input.get_single_mut() {
+                match typed.parse::<u64>() {
+                    Ok(value) => seed.0 = value,
+                    Err(_) => {
+                        if let Some(section) = text.sections.first_mut() {
+                            section.value = format!("Seed: {typed} isn't a valid seed");
+                        }
+                        continue;
+                    }
+                }
+            }
+            info!("Starting run with seed {}", seed.0);
+            commands.insert_resource(RunRngs::new(*seed));
             let _ = app_state.set(AppState::Overworld);
+        } else if val == "continue" {
+            if let (Some(snapshot), Some(grammar)) =
+                (RunSnapshot::load(), stories.get(&assets.story))
+            {
+                info!("Continuing run with seed {}", snapshot.seed);
+                let state = snapshot.restore(&mut commands, grammar, &cards);
+                let _ = app_state.set(state);
+            }
         }
     }
 }
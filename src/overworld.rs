@@ -1,12 +1,14 @@
 use bevy::prelude::*;
 
 use bevy_generative_grammars::tracery::TraceryGrammar;
-use bevy_turborand::RngComponent;
 
 use crate::{
     assets,
     card::{AvailableCards, Cards},
     game_state::AppState,
+    run_seed::{RunRngs, RunSeed},
+    save::{ResumedRun, RunSnapshot},
+    scene::scenario::ActorResources,
     story::*,
     ui::*,
 };
@@ -29,23 +31,50 @@ fn setup_overworld(
     current_scenario: Option<Res<Scenario>>,
     mut available_cards: ResMut<AvailableCards>,
     cards: Res<Cards>,
+    mut rngs: ResMut<RunRngs>,
+    seed: Res<RunSeed>,
+    resumed: Option<Res<ResumedRun>>,
+    resources: Res<ActorResources>,
 ) {
     let (story, scenario) = if let Some(mut story) = story {
         let current_scenario = current_scenario.map(|s| s.into_inner());
-        let scenario = story.generate_next_scenario(current_scenario);
+        let scenario = if resumed.is_some() {
+            commands.remove_resource::<ResumedRun>();
+            current_scenario
+                .cloned()
+                .ok_or_else(|| "The saved run has no scenario to resume".to_string())
+        } else {
+            story.generate_next_scenario(current_scenario)
+        };
         (Some(story.to_owned()), scenario)
     } else {
-        let mut rng = RngComponent::new();
         if let Some(asset) = stories.get(&assets.story) {
             available_cards.cards = cards.available_cards.clone();
-            let mut story = Story::generate(&mut rng, asset);
+            let mut story = Story::generate(&mut rngs.story, asset);
             let scenario = story.generate_next_scenario(None);
             (Some(story.to_owned()), scenario)
         } else {
-            (None, None)
+            (None, Err("The story grammar isn't loaded".to_string()))
         }
     };
 
+    if let Some(story) = &story {
+        if StoryPhase::Complete == story.phase {
+            RunSnapshot::clear();
+        } else {
+            RunSnapshot::capture(
+                *seed,
+                &rngs,
+                story,
+                scenario.as_ref().ok(),
+                &available_cards,
+                &resources,
+                None,
+            )
+            .save();
+        }
+    }
+
     bevy::log::info!("Setup Overworld UI");
 
     if let Some(story) = &story {
@@ -55,22 +84,36 @@ fn setup_overworld(
                 MainText::new("The End").size(100.).spawn(parent, &assets);
                 MenuButton::Primary.spawn("end", "Back To Menu", parent, &assets);
             });
-        } else if let Some(scenario) = &scenario {
-            commands.insert_resource(scenario.clone());
-            UiRoot::spawn(&mut commands, |parent| {
-                MainText::new(&scenario.initial_description)
-                    .size(30.)
-                    .alignment(JustifyContent::Center)
-                    .spawn(parent, &assets);
-                MenuButton::Primary.spawn("start_scenario", "Start Scenario", parent, &assets);
-            });
         } else {
-            UiRoot::spawn(&mut commands, |parent| {
-                MainText::new("Error loading scenario....")
-                    .size(100.)
-                    .spawn(parent, &assets);
-                MenuButton::Primary.spawn("end", "Back To Menu", parent, &assets);
-            });
+            match &scenario {
+                Ok(scenario) => {
+                    commands.insert_resource(scenario.clone());
+                    UiRoot::spawn(&mut commands, |parent| {
+                        MainText::new(&scenario.initial_description)
+                            .size(30.)
+                            .alignment(JustifyContent::Center)
+                            .spawn(parent, &assets);
+                        MenuButton::Primary.spawn(
+                            "start_scenario",
+                            "Start Scenario",
+                            parent,
+                            &assets,
+                        );
+                    });
+                }
+                Err(error) => {
+                    UiRoot::spawn(&mut commands, |parent| {
+                        MainText::new("Error loading scenario....")
+                            .size(100.)
+                            .spawn(parent, &assets);
+                        MainText::new(error)
+                            .size(20.)
+                            .alignment(JustifyContent::Center)
+                            .spawn(parent, &assets);
+                        MenuButton::Primary.spawn("end", "Back To Menu", parent, &assets);
+                    });
+                }
+            }
         }
     } else {
         UiRoot::spawn(&mut commands, |parent| {
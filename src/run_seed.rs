@@ -0,0 +1,93 @@
+use bevy::prelude::Resource;
+use bevy_turborand::{DelegatedRng, RngComponent};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct RunSeed(pub u64);
+
+#[derive(Debug, Clone, Copy)]
+pub enum RngStream {
+    Story,
+    Map,
+    EnemyHands,
+    EnemyAi,
+    Deck,
+}
+
+impl RunSeed {
+    pub fn random() -> Self {
+        Self(RngComponent::new().u64(..))
+    }
+
+    pub fn from_args<T: Iterator<Item = String>>(mut args: T) -> Option<Self> {
+        while let Some(arg) = args.next() {
+            if arg == "--seed" {
+                return args.next().and_then(|seed| seed.parse().ok()).map(Self);
+            } else if let Some(seed) = arg.strip_prefix("--seed=") {
+                return seed.parse().ok().map(Self);
+            }
+        }
+        None
+    }
+
+    pub fn stream(&self, stream: RngStream) -> RngComponent {
+        let stream_id = match stream {
+            RngStream::Story => 1,
+            RngStream::Map => 2,
+            RngStream::EnemyHands => 3,
+            RngStream::EnemyAi => 4,
+            RngStream::Deck => 5,
+        };
+        RngComponent::with_seed(split_mix(self.0 ^ split_mix(stream_id)))
+    }
+}
+
+// SplitMix64 - a fixed mixing function, so sub-seeds stay stable across platforms and releases
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+pub struct RunRngs {
+    pub story: RngComponent,
+    pub map: RngComponent,
+    pub enemy_hands: RngComponent,
+    pub enemy_ai: RngComponent,
+    pub deck: RngComponent,
+}
+
+impl RunRngs {
+    pub fn new(seed: RunSeed) -> Self {
+        Self {
+            story: seed.stream(RngStream::Story),
+            map: seed.stream(RngStream::Map),
+            enemy_hands: seed.stream(RngStream::EnemyHands),
+            enemy_ai: seed.stream(RngStream::EnemyAi),
+            deck: seed.stream(RngStream::Deck),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_streams_continue_where_they_left_off() {
+        let mut rngs = RunRngs::new(RunSeed(7));
+        for _ in 0..10 {
+            let _ = rngs.map.u64(..);
+            let _ = rngs.enemy_ai.u64(..);
+        }
+        let json = serde_json::to_string(&rngs).unwrap();
+        let mut resumed = serde_json::from_str::<RunRngs>(&json).unwrap();
+        for _ in 0..10 {
+            assert_eq!(rngs.map.u64(..), resumed.map.u64(..));
+            assert_eq!(rngs.enemy_ai.u64(..), resumed.enemy_ai.u64(..));
+            assert_eq!(rngs.story.u64(..), resumed.story.u64(..));
+        }
+    }
+}
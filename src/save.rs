@@ -0,0 +1,295 @@
+mod storage;
+
+use bevy::prelude::*;
+use bevy_generative_grammars::tracery::TraceryGrammar;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{AvailableCards, Cards},
+    game_state::AppState,
+    run_seed::{RunRngs, RunSeed},
+    scene::{
+        scenario::{
+            Actor, ActorPosition, ActorResource, ActorResources, CurrentTurnProcess, Goal,
+            ScenarioMap,
+        },
+        ResumeScene, SceneState,
+    },
+    story::{Scenario, Story, StoryPhase},
+};
+
+const RUN_SAVE: &str = "run";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SuspendBattleEvent>()
+            .add_system_set(
+                SystemSet::on_enter(SceneState::Intermission).with_system(save_at_intermission),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Scene).with_system(suspend_battle));
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct ResumedRun;
+
+#[derive(Debug, Clone)]
+pub struct SuspendBattleEvent;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub map: ScenarioMap,
+    pub actors: Vec<(Actor, ActorResource)>,
+    pub turn_order: Vec<Actor>,
+    pub positions: Vec<(Actor, ActorPosition)>,
+    pub goals: Vec<Goal>,
+    pub process: Option<CurrentTurnProcess>,
+}
+
+impl BoardSnapshot {
+    pub fn capture(
+        map: &ScenarioMap,
+        resources: &ActorResources,
+        mut positions: Vec<(Actor, ActorPosition)>,
+        mut goals: Vec<Goal>,
+        process: Option<CurrentTurnProcess>,
+    ) -> Self {
+        let mut actors = resources
+            .resources
+            .iter()
+            .map(|(actor, resource)| (*actor, resource.clone()))
+            .collect::<Vec<_>>();
+        actors.sort_by_key(|(actor, _)| *actor);
+        positions.sort_by_key(|(actor, _)| *actor);
+        goals.sort_by_key(|goal| goal.number);
+        Self {
+            map: map.clone(),
+            actors,
+            turn_order: resources.turn_order.clone(),
+            positions,
+            goals,
+            process,
+        }
+    }
+
+    pub fn resources(&self) -> ActorResources {
+        ActorResources {
+            resources: self.actors.iter().cloned().collect(),
+            turn_order: self.turn_order.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSnapshot {
+    pub seed: u64,
+    pub phase: StoryPhase,
+    pub scenarios: Vec<Scenario>,
+    pub generated_scenarios: usize,
+    pub current_scenario: Option<Scenario>,
+    pub available_cards: Vec<String>,
+    pub rngs: RunRngs,
+    pub player: Option<ActorResource>,
+    pub board: Option<BoardSnapshot>,
+}
+
+impl RunSnapshot {
+    pub fn capture(
+        seed: RunSeed,
+        rngs: &RunRngs,
+        story: &Story,
+        scenario: Option<&Scenario>,
+        available_cards: &AvailableCards,
+        resources: &ActorResources,
+        board: Option<BoardSnapshot>,
+    ) -> Self {
+        let mut available = available_cards.cards.keys().cloned().collect::<Vec<_>>();
+        available.sort();
+        Self {
+            seed: seed.0,
+            phase: story.phase,
+            scenarios: story.scenarios.clone(),
+            generated_scenarios: story.generated_scenarios,
+            current_scenario: scenario.cloned(),
+            available_cards: available,
+            rngs: rngs.clone(),
+            player: resources.resources.get(&Actor::Player).cloned(),
+            board,
+        }
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                if let Err(e) = storage::write(RUN_SAVE, &json) {
+                    error!("Couldn't save run - {e}");
+                } else {
+                    info!("Run saved");
+                }
+            }
+            Err(e) => error!("Couldn't serialize run - {e}"),
+        }
+    }
+
+    pub fn load() -> Option<Self> {
+        let json = storage::read(RUN_SAVE)?;
+        match serde_json::from_str(&json) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                warn!("Couldn't read saved run - {e}");
+                None
+            }
+        }
+    }
+
+    pub fn clear() {
+        storage::remove(RUN_SAVE);
+    }
+
+    pub fn restore(
+        self,
+        commands: &mut Commands,
+        grammar: &TraceryGrammar,
+        cards: &Cards,
+    ) -> AppState {
+        let seed = RunSeed(self.seed);
+        // The story draws from its own copy of the story stream, so the saved one is still where
+        // the run started - replaying it also rebuilds the variables the grammar has set
+        let story = Story::resume(
+            &mut self.rngs.story.clone(),
+            grammar,
+            self.generated_scenarios,
+            self.phase,
+            self.scenarios,
+        );
+        commands.insert_resource(seed);
+        commands.insert_resource(self.rngs);
+        commands.insert_resource(story);
+        commands.insert_resource(AvailableCards {
+            cards: self
+                .available_cards
+                .iter()
+                .filter_map(|id| cards.cards.get(id).map(|card| (id.clone(), card.clone())))
+                .collect(),
+        });
+
+        if let Some(scenario) = self.current_scenario {
+            commands.insert_resource(scenario);
+        } else {
+            commands.remove_resource::<Scenario>();
+        }
+
+        if let Some(board) = self.board {
+            commands.insert_resource(board.resources());
+            commands.insert_resource(ResumeScene {
+                state: if board.process.is_some() {
+                    SceneState::Processing
+                } else {
+                    SceneState::Intermission
+                },
+                positions: board.positions,
+                goals: board.goals,
+                process: board.process,
+            });
+            commands.insert_resource(board.map);
+            AppState::Scene
+        } else {
+            commands.insert_resource(ActorResources {
+                resources: self
+                    .player
+                    .map(|player| (Actor::Player, player))
+                    .into_iter()
+                    .collect(),
+                turn_order: vec![],
+            });
+            commands.insert_resource(ResumedRun);
+            AppState::Overworld
+        }
+    }
+}
+
+fn save_at_intermission(
+    seed: Res<RunSeed>,
+    rngs: Res<RunRngs>,
+    story: Option<Res<Story>>,
+    scenario: Option<Res<Scenario>>,
+    available_cards: Res<AvailableCards>,
+    map: Option<Res<ScenarioMap>>,
+    resources: Res<ActorResources>,
+    positions: Query<(&Actor, &ActorPosition)>,
+    goals: Query<&Goal>,
+) {
+    if let (Some(story), Some(map)) = (story, map) {
+        let board = BoardSnapshot::capture(
+            &map,
+            &resources,
+            positions.iter().map(|(a, p)| (*a, *p)).collect(),
+            goals.iter().copied().collect(),
+            None,
+        );
+        RunSnapshot::capture(
+            *seed,
+            &rngs,
+            &story,
+            scenario.as_deref(),
+            &available_cards,
+            &resources,
+            Some(board),
+        )
+        .save();
+    }
+}
+
+fn suspend_battle(
+    mut commands: Commands,
+    mut events: EventReader<SuspendBattleEvent>,
+    mut app_state: ResMut<State<AppState>>,
+    seed: Res<RunSeed>,
+    rngs: Res<RunRngs>,
+    story: Option<Res<Story>>,
+    scenario: Option<Res<Scenario>>,
+    available_cards: Res<AvailableCards>,
+    map: Option<Res<ScenarioMap>>,
+    resources: Res<ActorResources>,
+    process: Res<CurrentTurnProcess>,
+    scene_state: Res<State<SceneState>>,
+    positions: Query<(&Actor, &ActorPosition)>,
+    goals: Query<&Goal>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+    if let (Some(story), Some(map)) = (story, map) {
+        info!("Suspending battle");
+        // Once a turn has started, resume it as a resolved card so it doesn't start again
+        let process = match (scene_state.current(), process.clone()) {
+            (
+                SceneState::PlayerTurn | SceneState::EnemyTurn,
+                CurrentTurnProcess::Thinking(actor),
+            ) => CurrentTurnProcess::CardResolved(actor),
+            (_, process) => process,
+        };
+        let board = BoardSnapshot::capture(
+            &map,
+            &resources,
+            positions.iter().map(|(a, p)| (*a, *p)).collect(),
+            goals.iter().copied().collect(),
+            Some(process),
+        );
+        RunSnapshot::capture(
+            *seed,
+            &rngs,
+            &story,
+            scenario.as_deref(),
+            &available_cards,
+            &resources,
+            Some(board),
+        )
+        .save();
+        commands.remove_resource::<Story>();
+        commands.remove_resource::<Scenario>();
+        let _ = app_state.set(AppState::MainMenu);
+    }
+}
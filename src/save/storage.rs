@@ -0,0 +1,56 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::{fs, path::PathBuf};
+
+    const SAVE_DIRECTORY: &str = "saves";
+
+    fn path(key: &str) -> PathBuf {
+        PathBuf::from(SAVE_DIRECTORY).join(format!("{key}.json"))
+    }
+
+    pub fn write(key: &str, value: &str) -> Result<(), String> {
+        fs::create_dir_all(SAVE_DIRECTORY).map_err(|e| e.to_string())?;
+        fs::write(path(key), value).map_err(|e| e.to_string())
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        fs::read_to_string(path(key)).ok()
+    }
+
+    pub fn remove(key: &str) {
+        let _ = fs::remove_file(path(key));
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    fn storage_key(key: &str) -> String {
+        format!("intermission-{key}")
+    }
+
+    pub fn write(key: &str, value: &str) -> Result<(), String> {
+        storage()
+            .ok_or_else(|| "Local storage is unavailable".to_string())?
+            .set_item(&storage_key(key), value)
+            .map_err(|e| format!("{e:?}"))
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        storage()?.get_item(&storage_key(key)).ok()?
+    }
+
+    pub fn remove(key: &str) {
+        if let Some(storage) = storage() {
+            let _ = storage.remove_item(&storage_key(key));
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
@@ -1,13 +1,15 @@
 pub mod board;
+mod card_choice;
 mod intermission_phase;
 mod player_turn;
-mod scenario;
+pub mod scenario;
 mod scenario_fail;
 mod scenario_sucess;
 mod setup_phase;
 
 use bevy::prelude::*;
 use board::*;
+use card_choice::CardChoicePlugin;
 use intermission_phase::*;
 use player_turn::PlayerTurnPlugin;
 use scenario::*;
@@ -26,6 +28,7 @@ impl Plugin for ScenePlugin {
             .add_plugin(BoardPlugin)
             .add_plugin(SetupPhasePlugin)
             .add_plugin(PlayerTurnPlugin)
+            .add_plugin(CardChoicePlugin)
             .add_plugin(IntermissionPhasePlugin)
             .add_plugin(ScenarioSuccessPlugin)
             .add_plugin(FailPhasePlugin)
@@ -45,10 +48,23 @@ pub enum SceneState {
     Succeeded,
     Failed,
     Intermission,
+    Resuming,
 }
 
-fn setup_scene(mut scene_state: ResMut<State<SceneState>>) {
-    let _ = scene_state.set(SceneState::Setup);
+#[derive(Debug, Clone, Resource)]
+pub struct ResumeScene {
+    pub state: SceneState,
+    pub positions: Vec<(Actor, ActorPosition)>,
+    pub goals: Vec<Goal>,
+    pub process: Option<CurrentTurnProcess>,
+}
+
+fn setup_scene(mut scene_state: ResMut<State<SceneState>>, resume: Option<Res<ResumeScene>>) {
+    if resume.is_some() {
+        let _ = scene_state.set(SceneState::Resuming);
+    } else {
+        let _ = scene_state.set(SceneState::Setup);
+    }
 }
 
 fn end_scene(mut scene_state: ResMut<State<SceneState>>) {
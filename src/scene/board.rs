@@ -1,10 +1,13 @@
 mod attack_action;
 pub mod board_assets;
 mod continue_action;
+mod effect_action;
 mod move_action;
 mod selection_actions;
 mod set_turn_process_action;
-mod stun_action;
+mod status_action;
+mod status_labels;
+mod teleport_action;
 mod wait_action;
 
 use bevy::prelude::*;
@@ -15,57 +18,77 @@ use smooth_bevy_cameras::LookTransform;
 
 use super::scenario::{
     scenario_map::{self, *},
-    types::{ActorResources, AdjustActorEvent},
-    Actor, ActorPosition, AnimateActionsEvents, Goal, GoalStatus,
+    simulation,
+    status::StatusKind,
+    types::{ActorResource, ActorResources, AdjustActorEvent},
+    Actor, ActorPosition, AnimateActionsEvents, CurrentTurnProcess, Goal, GoalStatus,
 };
+use crate::enemy::Enemies;
 use crate::game_state::AppState;
-use crate::scene::SceneState;
+use crate::scene::{ResumeScene, SceneState};
+use crate::story::{Scenario, ScenarioState};
 
 use selection_actions::*;
 use set_turn_process_action::*;
 
-use self::board_assets::BoardAssets;
+use self::board_assets::{BoardAssets, TerrainMaterials};
 
 pub struct BoardPlugin;
 
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_system_set(
-            SystemSet::on_enter(SceneState::None)
-                .with_system(clear_board)
-                .with_system(reset_camera),
-        )
-        .add_system_set(
-            SystemSet::on_enter(SceneState::Succeeded)
-                .with_system(clear_board)
-                .with_system(reset_camera),
-        )
-        .add_system_set(
-            SystemSet::on_enter(SceneState::Failed)
-                .with_system(clear_board)
-                .with_system(reset_camera),
-        )
-        .add_system_set(
-            SystemSet::on_update(SceneState::Setup)
-                .with_system(generate_board)
-                .with_system(set_camera),
-        )
-        .add_system_set(
-            SystemSet::on_update(AppState::Scene)
-                .with_system(animate_actions)
-                .with_system(wait_action::wait_system)
-                .with_system(continue_action::continue_system)
-                .with_system(setup_selectable)
-                .with_system(process_selection_events)
-                .with_system(set_selection)
-                .with_system(move_action::move_system)
-                .with_system(attack_action::attack_system)
-                .with_system(stun_action::stun_system)
-                .with_system(set_turn_process_system)
-                .with_system(draw_active_goal)
-                .with_system(apply_changes_to_actors)
-                .with_system(react_to_actor_events),
-        );
+        app.init_resource::<TerrainMaterials>()
+            .add_system_set(
+                SystemSet::on_enter(SceneState::None)
+                    .with_system(clear_board)
+                    .with_system(status_labels::clear_status_labels)
+                    .with_system(reset_camera),
+            )
+            .add_system_set(
+                SystemSet::on_enter(SceneState::Succeeded)
+                    .with_system(clear_board)
+                    .with_system(status_labels::clear_status_labels)
+                    .with_system(reset_camera),
+            )
+            .add_system_set(
+                SystemSet::on_enter(SceneState::Failed)
+                    .with_system(clear_board)
+                    .with_system(status_labels::clear_status_labels)
+                    .with_system(reset_camera),
+            )
+            .add_system_set(
+                SystemSet::on_update(SceneState::Setup)
+                    .with_system(generate_board)
+                    .with_system(set_camera),
+            )
+            .add_system_set(
+                SystemSet::on_update(SceneState::Resuming)
+                    .with_system(generate_board)
+                    .with_system(set_camera)
+                    .with_system(finish_resume),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Scene)
+                    .with_system(animate_actions)
+                    .with_system(wait_action::wait_system)
+                    .with_system(continue_action::continue_system)
+                    .with_system(setup_selectable)
+                    .with_system(process_selection_events)
+                    .with_system(preview_affected_tiles)
+                    .with_system(set_selection)
+                    .with_system(move_action::move_system)
+                    .with_system(attack_action::attack_system)
+                    .with_system(status_action::status_system)
+                    .with_system(effect_action::effect_system)
+                    .with_system(teleport_action::teleport_system)
+                    .with_system(set_turn_process_system)
+                    .with_system(draw_active_goal)
+                    .with_system(update_tile_entities)
+                    .with_system(apply_changes_to_actors)
+                    .with_system(status_labels::update_status_labels)
+                    .with_system(status_labels::position_status_labels)
+                    .with_system(react_to_actor_events),
+            );
     }
 }
 
@@ -81,15 +104,32 @@ fn clear_board(mut commands: Commands, query: Query<Entity, With<Board>>) {
 fn generate_board(
     mut commands: Commands,
     assets: Res<board_assets::BoardAssets>,
-    scenario_map: Option<Res<ScenarioMap>>,
+    terrain: Res<TerrainMaterials>,
+    scenario_map: Option<ResMut<ScenarioMap>>,
+    scenario: Option<Res<Scenario>>,
+    resume: Option<Res<ResumeScene>>,
+    resources: Option<Res<ActorResources>>,
+    enemies: Res<Enemies>,
+    asset_server: Res<AssetServer>,
 ) {
-    if let Some(scenario_map) = scenario_map {
+    if let Some(mut scenario_map) = scenario_map {
         if !scenario_map.is_changed() {
             return;
         }
 
+        let active_goal = match scenario.as_ref().map(|s| &s.state) {
+            Some(ScenarioState::InProgress(goal)) => *goal,
+            _ => 0,
+        };
+
+        let (positions, saved_goals) = match resume {
+            Some(resume) => (resume.positions.clone(), resume.goals.clone()),
+            None => (simulation::start_positions(&scenario_map), vec![]),
+        };
+
         let left = -1. * scenario_map.width as f32 / 2.;
         let top = -1. * scenario_map.height as f32 / 2.;
+        let mut tile_entities = vec![];
 
         commands
             .spawn((
@@ -119,73 +159,171 @@ fn generate_board(
                         _ => None,
                     };
 
-                    match tile.tile_type {
-                        scenario_map::TileType::Empty => {}
-                        scenario_map::TileType::Floor => {
-                            let mut tile = parent.spawn(PbrBundle {
-                                mesh: assets.tile.clone(),
-                                material: assets.tile_mat.clone(),
-                                transform: Transform::from_xyz(pos.0, 0., pos.1),
-                                ..Default::default()
-                            });
-                            if let Some(goal_id) = goal_id {
-                                tile.insert(Goal {
-                                    number: goal_id,
-                                    status: if goal_id == 0 {
-                                        GoalStatus::Active
-                                    } else {
-                                        GoalStatus::Hidden
-                                    },
-                                });
-                            }
-                        }
-                        TileType::Obstacle => {
-                            parent.spawn(PbrBundle {
-                                mesh: assets.obstacle.clone(),
-                                material: assets.obstacle_mat.clone(),
-                                transform: Transform::from_xyz(pos.0, 0., pos.1),
-                                ..Default::default()
-                            });
-                        }
-                        TileType::Wall => {
-                            parent.spawn(PbrBundle {
-                                mesh: assets.wall.clone(),
-                                material: assets.wall_mat.clone(),
+                    if let Some((mesh, material)) = tile_visual(tile, &assets, &terrain) {
+                        let mut tile_entity = parent.spawn((
+                            PbrBundle {
+                                mesh,
+                                material,
                                 transform: Transform::from_xyz(pos.0, 0., pos.1),
                                 ..Default::default()
-                            });
-                        }
-                    }
-
-                    match tile.tag {
-                        TileTag::Start => {
-                            parent.spawn((
-                                PbrBundle {
-                                    mesh: assets.player.clone(),
-                                    material: assets.player_mat.clone(),
-                                    transform: Transform::from_xyz(pos.0, 0., pos.1),
-                                    ..Default::default()
-                                },
-                                Actor::Player,
-                                ActorPosition(tile.pos.0, tile.pos.1),
-                            ));
-                        }
-                        scenario_map::TileTag::Enemy(actor) => {
-                            parent.spawn((
-                                PbrBundle {
-                                    mesh: assets.monster.clone(),
-                                    material: assets.monster_mat.clone(),
-                                    transform: Transform::from_xyz(pos.0, 0., pos.1),
-                                    ..Default::default()
+                            },
+                            BoardTile(tile.tile_type),
+                        ));
+                        if let Some(goal_id) = goal_id {
+                            let saved = saved_goals.iter().find(|g| g.number == goal_id);
+                            tile_entity.insert(saved.copied().unwrap_or(Goal {
+                                number: goal_id,
+                                status: match goal_id.cmp(&active_goal) {
+                                    std::cmp::Ordering::Less => GoalStatus::Completed,
+                                    std::cmp::Ordering::Equal => GoalStatus::Active,
+                                    std::cmp::Ordering::Greater => GoalStatus::Hidden,
                                 },
-                                actor,
-                                ActorPosition(tile.pos.0, tile.pos.1),
-                            ));
+                            }));
                         }
-                        _ => {}
+                        tile_entities.push((tile.pos, tile_entity.id()));
                     }
                 }
+
+                for (actor, position) in positions.iter() {
+                    let actor_resource = resources.as_ref().and_then(|r| r.resources.get(actor));
+                    spawn_actor(
+                        parent,
+                        actor,
+                        position,
+                        actor_resource,
+                        &assets,
+                        &enemies,
+                        &asset_server,
+                    );
+                }
             });
+
+        let scenario_map = scenario_map.bypass_change_detection();
+        for (pos, entity) in tile_entities {
+            if let Some(tile) = scenario_map.tiles.iter_mut().find(|tile| tile.pos == pos) {
+                tile.entity = Some(entity);
+            }
+        }
+    }
+}
+
+fn spawn_actor(
+    parent: &mut ChildBuilder,
+    actor: &Actor,
+    position: &ActorPosition,
+    actor_resource: Option<&ActorResource>,
+    assets: &BoardAssets,
+    enemies: &Enemies,
+    asset_server: &AssetServer,
+) {
+    let definition = actor_resource
+        .and_then(|r| r.enemy.as_ref())
+        .and_then(|id| enemies.enemies.get(id));
+    let (mesh, material) = match (actor, definition) {
+        (Actor::Player, _) => (assets.player.clone(), assets.player_mat.clone()),
+        (Actor::Enemy(_), Some(definition)) => (
+            asset_server.load(definition.model_path().as_str()),
+            asset_server.load(definition.material_path().as_str()),
+        ),
+        (Actor::Enemy(_), None) => (assets.monster.clone(), assets.monster_mat.clone()),
+    };
+    parent.spawn((
+        PbrBundle {
+            mesh,
+            material,
+            transform: Transform::from_xyz(position.0 as f32, 0., position.1 as f32),
+            ..Default::default()
+        },
+        *actor,
+        *position,
+    ));
+}
+
+fn tile_visual(
+    tile: &Tile,
+    assets: &BoardAssets,
+    terrain: &TerrainMaterials,
+) -> Option<(Handle<Mesh>, Handle<StandardMaterial>)> {
+    match tile.tile_type {
+        TileType::Empty => None,
+        TileType::Floor => Some((assets.tile.clone(), assets.tile_mat.clone())),
+        TileType::Difficult => Some((assets.tile.clone(), terrain.difficult.clone())),
+        TileType::Hazard => Some((assets.tile.clone(), terrain.hazard.clone())),
+        TileType::Spring => Some((assets.tile.clone(), terrain.spring.clone())),
+        TileType::Ice => Some((assets.tile.clone(), terrain.ice.clone())),
+        TileType::Obstacle => Some((assets.obstacle.clone(), assets.obstacle_mat.clone())),
+        TileType::Wall => Some((assets.wall.clone(), assets.wall_mat.clone())),
+    }
+}
+
+#[derive(Component)]
+struct BoardTile(TileType);
+
+fn update_tile_entities(
+    mut commands: Commands,
+    scenario_map: Option<Res<ScenarioMap>>,
+    assets: Res<BoardAssets>,
+    terrain: Res<TerrainMaterials>,
+    mut tiles: Query<(&mut BoardTile, &mut Transform)>,
+) {
+    let scenario_map = match scenario_map {
+        Some(scenario_map) if scenario_map.is_changed() => scenario_map,
+        _ => return,
+    };
+    for tile in scenario_map.tiles.iter() {
+        let entity = match tile.entity {
+            Some(entity) => entity,
+            None => continue,
+        };
+        if let Ok((mut board_tile, mut transform)) = tiles.get_mut(entity) {
+            if board_tile.0 != tile.tile_type {
+                board_tile.0 = tile.tile_type;
+                if let Some((mesh, material)) = tile_visual(tile, &assets, &terrain) {
+                    commands.entity(entity).insert((mesh, material));
+                }
+            }
+            let health = tile.tile_type.rules().health.unwrap_or(0);
+            transform.scale.y = if health > 0 {
+                1. - 0.5 * (tile.damage.min(health) as f32 / health as f32)
+            } else {
+                1.
+            };
+        }
+    }
+}
+
+fn finish_resume(
+    mut commands: Commands,
+    mut scene_state: ResMut<State<SceneState>>,
+    resume: Option<Res<ResumeScene>>,
+    board: Query<Entity, With<Board>>,
+    mut animate: EventWriter<AnimateActionsEvents>,
+) {
+    if let Some(resume) = resume {
+        if board.is_empty() {
+            return;
+        }
+        info!("Resuming scene at {:?}", resume.state);
+        let _ = scene_state.set(resume.state.clone());
+        if let Some(process) = &resume.process {
+            info!("Resuming turn process {:?}", process);
+            commands.insert_resource(process.clone());
+            match process {
+                // The turn hasn't started yet, so continue into it as usual
+                CurrentTurnProcess::Thinking(actor) => {
+                    animate.send(AnimateActionsEvents::Wait(0.2));
+                    animate.send(AnimateActionsEvents::Continue(*actor));
+                }
+                // Mid turn - next_turn_ready picks these up without starting the turn again
+                CurrentTurnProcess::CardResolved(_) | CurrentTurnProcess::Done(_) => {}
+                // Mid card - the card systems react to the process being inserted
+                CurrentTurnProcess::CardActionTriggered(_, _, _)
+                | CurrentTurnProcess::CardChoice(_, _, _)
+                | CurrentTurnProcess::CardTargetsSelected(_, _, _, _)
+                | CurrentTurnProcess::None => {}
+            }
+        }
+        commands.remove_resource::<ResumeScene>();
     }
 }
 
@@ -238,33 +376,61 @@ fn animate_actions(
                         target_selection.clone(),
                     ));
                 }
-                AnimateActionsEvents::Move(actor, position) => {
+                AnimateActionsEvents::Move(actor, path) => {
                     bevy::log::info!("Move To Target");
                     actions.add(move_action::MoveAction {
                         actor: *actor,
-                        position: *position,
+                        path: path.clone(),
                         speed: 10.,
                     });
                 }
                 AnimateActionsEvents::SetTurnProcess(p) => {
                     actions.add(set_turn_process_action::SetTurnProcessAction(p.clone()));
                 }
-                AnimateActionsEvents::Attack(actor, target, damage) => {
+                AnimateActionsEvents::Attack(actor, impact) => {
                     actions.add(attack_action::AttackAction {
                         actor: *actor,
-                        target: *target,
-                        damage: *damage,
+                        impact: impact.clone(),
                         duration: 0.5,
                     });
                 }
-                AnimateActionsEvents::Stun(actor, target, stun_duration) => {
-                    actions.add(stun_action::StunAction {
+                AnimateActionsEvents::ApplyStatus(actor, impact) => {
+                    actions.add(status_action::StatusAction {
+                        actor: *actor,
+                        impact: impact.clone(),
+                        duration: 0.5,
+                    });
+                }
+                AnimateActionsEvents::Push(actor, path) => {
+                    actions.add(move_action::MoveAction {
+                        actor: *actor,
+                        path: path.clone(),
+                        speed: 15.,
+                    });
+                }
+                AnimateActionsEvents::Teleport(actor, target) => {
+                    actions.add(teleport_action::TeleportAction {
                         actor: *actor,
                         target: *target,
-                        stun_duration: *stun_duration,
                         duration: 0.5,
                     });
                 }
+                AnimateActionsEvents::Heal(actor, impact)
+                | AnimateActionsEvents::AreaAttack(actor, impact)
+                | AnimateActionsEvents::Summon(actor, impact) => {
+                    actions.add(effect_action::EffectAction {
+                        actor: *actor,
+                        impact: impact.clone(),
+                        duration: 0.5,
+                    });
+                }
+                AnimateActionsEvents::Draw(actor, impact) => {
+                    actions.add(effect_action::EffectAction {
+                        actor: *actor,
+                        impact: impact.clone(),
+                        duration: 0.3,
+                    });
+                }
             }
         }
     }
@@ -305,7 +471,7 @@ fn apply_changes_to_actors(
             transform.translation =
                 Vec3::new(transform.translation.x, 0.1, transform.translation.z);
         } else if let Some(res) = resources.resources.get(actor) {
-            if res.stun_duration > 0 {
+            if res.has_status(StatusKind::Stun) {
                 transform.scale = Vec3::new(transform.scale.x, 0.2, transform.scale.z);
                 transform.translation =
                     Vec3::new(transform.translation.x, 0.1, transform.translation.z);
@@ -319,8 +485,30 @@ fn apply_changes_to_actors(
 }
 
 fn react_to_actor_events(
-    _commands: Commands,
-    _query: Query<(&Actor, &Transform)>,
-    _events: EventReader<AdjustActorEvent>,
+    mut commands: Commands,
+    board: Query<Entity, With<Board>>,
+    mut events: EventReader<AdjustActorEvent>,
+    assets: Res<BoardAssets>,
+    enemies: Res<Enemies>,
+    asset_server: Res<AssetServer>,
 ) {
+    let board = match board.get_single() {
+        Ok(board) => board,
+        Err(_) => return,
+    };
+    for event in events.iter() {
+        if let AdjustActorEvent::Summon(actor, position, resource) = event {
+            commands.entity(board).with_children(|parent| {
+                spawn_actor(
+                    parent,
+                    actor,
+                    position,
+                    Some(resource),
+                    &assets,
+                    &enemies,
+                    &asset_server,
+                );
+            });
+        }
+    }
 }
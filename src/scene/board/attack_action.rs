@@ -10,10 +10,9 @@ use bevy_sequential_actions::Action;
 use crate::scene::scenario::*;
 
 pub struct AttackAction {
-    pub(crate) target: ActorPosition,
     pub(crate) actor: Actor,
     pub(crate) duration: f32,
-    pub(crate) damage: usize,
+    pub(crate) impact: Impact,
 }
 
 impl Action for AttackAction {
@@ -21,10 +20,9 @@ impl Action for AttackAction {
         // Run the wait system on the agent
         world.entity_mut(agent).insert(Attack(
             self.actor,
-            self.target,
             self.duration,
             None,
-            self.damage,
+            self.impact.clone(),
         ));
     }
 
@@ -35,35 +33,35 @@ impl Action for AttackAction {
 }
 
 #[derive(Component)]
-pub(crate) struct Attack(Actor, ActorPosition, f32, Option<f32>, usize);
+pub(crate) struct Attack(Actor, f32, Option<f32>, Impact);
 
 pub(crate) fn attack_system(
     mut attack_q: Query<(&mut Attack, &mut ActionFinished)>,
-    mut actors: Query<(&Actor, &ActorPosition, &mut Transform)>,
+    mut actors: Query<(&Actor, &mut Transform)>,
     mut events: EventWriter<AdjustActorEvent>,
+    mut tile_events: EventWriter<AdjustTileEvent>,
     time: Res<Time>,
 ) {
     for (mut attack, mut finished) in attack_q.iter_mut() {
-        if attack.3.is_none() {
-            attack.3 = Some(time.elapsed_seconds());
+        if attack.2.is_none() {
+            attack.2 = Some(time.elapsed_seconds());
         }
-        if let Some(start) = attack.3 {
+        if let Some(start) = attack.2 {
             let elapsed = time.elapsed_seconds() - start;
-            let complete = elapsed >= attack.2;
+            let complete = elapsed >= attack.1;
             if complete {
                 finished.confirm_and_reset();
+                events.send_batch(attack.3.actors.iter().cloned());
+                tile_events.send_batch(attack.3.tiles.iter().cloned());
             }
-            for (actor, pos, mut transform) in actors.iter_mut() {
+            for (actor, mut transform) in actors.iter_mut() {
                 if actor == &attack.0 {
                     if complete {
                         transform.scale = Vec3::splat(1.);
                     } else {
-                        transform.scale = Vec3::splat(1. + (elapsed / attack.2) * 0.5);
+                        transform.scale = Vec3::splat(1. + (elapsed / attack.1) * 0.5);
                     }
                 }
-                if complete && *pos == attack.1 && actor != &attack.0 {
-                    events.send(AdjustActorEvent::Damage(*actor, attack.4));
-                }
             }
         }
     }
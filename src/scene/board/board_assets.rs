@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
 #[derive(Default, AssetCollection, Resource)]
-pub(crate) struct BoardAssets {
+pub struct BoardAssets {
     #[asset(path = "models.gltf#Mesh0/Primitive0")]
     pub(crate) tile: Handle<Mesh>,
     #[asset(path = "models.gltf#Mesh1/Primitive0")]
@@ -38,3 +38,40 @@ pub(crate) struct BoardAssets {
     #[asset(path = "models.gltf#Material10")]
     pub(crate) selector_hover: Handle<StandardMaterial>,
 }
+
+#[derive(Resource)]
+pub struct TerrainMaterials {
+    pub(crate) difficult: Handle<StandardMaterial>,
+    pub(crate) hazard: Handle<StandardMaterial>,
+    pub(crate) spring: Handle<StandardMaterial>,
+    pub(crate) ice: Handle<StandardMaterial>,
+}
+
+impl FromWorld for TerrainMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        Self {
+            difficult: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.45, 0.35, 0.2),
+                perceptual_roughness: 1.,
+                ..Default::default()
+            }),
+            hazard: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.8, 0.25, 0.1),
+                emissive: Color::rgb(0.4, 0.1, 0.),
+                ..Default::default()
+            }),
+            spring: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.2, 0.7, 0.6),
+                emissive: Color::rgb(0., 0.15, 0.1),
+                ..Default::default()
+            }),
+            ice: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.75, 0.9, 1.),
+                perceptual_roughness: 0.1,
+                reflectance: 0.8,
+                ..Default::default()
+            }),
+        }
+    }
+}
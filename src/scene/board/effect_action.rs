@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy_sequential_actions::ActionFinished;
+
+use bevy_sequential_actions::StopReason;
+
+use bevy_sequential_actions::ActionCommands;
+
+use bevy_sequential_actions::Action;
+
+use crate::scene::scenario::*;
+
+pub struct EffectAction {
+    pub(crate) actor: Actor,
+    pub(crate) duration: f32,
+    pub(crate) impact: Impact,
+}
+
+impl Action for EffectAction {
+    fn on_start(&mut self, agent: Entity, world: &mut World, _commands: &mut ActionCommands) {
+        world.entity_mut(agent).insert(Effecting(
+            self.actor,
+            self.duration,
+            None,
+            self.impact.clone(),
+        ));
+    }
+
+    fn on_stop(&mut self, agent: Entity, world: &mut World, _reason: StopReason) {
+        let _effect = world.entity_mut(agent).remove::<Effecting>();
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct Effecting(Actor, f32, Option<f32>, Impact);
+
+pub(crate) fn effect_system(
+    mut effect_q: Query<(&mut Effecting, &mut ActionFinished)>,
+    mut actors: Query<(&Actor, &mut Transform)>,
+    mut events: EventWriter<AdjustActorEvent>,
+    mut tile_events: EventWriter<AdjustTileEvent>,
+    time: Res<Time>,
+) {
+    for (mut effect, mut finished) in effect_q.iter_mut() {
+        if effect.2.is_none() {
+            effect.2 = Some(time.elapsed_seconds());
+        }
+        let start = match effect.2 {
+            Some(start) => start,
+            None => continue,
+        };
+        let elapsed = time.elapsed_seconds() - start;
+        let complete = elapsed >= effect.1;
+        for (actor, mut transform) in actors.iter_mut() {
+            if actor == &effect.0 {
+                if complete {
+                    transform.scale = Vec3::splat(1.);
+                } else {
+                    transform.scale = Vec3::splat(1. + (elapsed / effect.1) * 0.5);
+                }
+            }
+        }
+        if !complete {
+            continue;
+        }
+        finished.confirm_and_reset();
+        events.send_batch(effect.3.actors.iter().cloned());
+        tile_events.send_batch(effect.3.tiles.iter().cloned());
+    }
+}
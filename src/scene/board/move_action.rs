@@ -10,7 +10,7 @@ use bevy_sequential_actions::Action;
 use crate::scene::scenario::*;
 
 pub struct MoveAction {
-    pub(crate) position: ActorPosition,
+    pub(crate) path: Vec<ActorPosition>,
     pub(crate) actor: Actor,
     pub(crate) speed: f32,
 }
@@ -20,7 +20,7 @@ impl Action for MoveAction {
         // Run the wait system on the agent
         world
             .entity_mut(agent)
-            .insert(Move(self.actor, self.position, self.speed));
+            .insert(Move(self.actor, self.path.clone(), 0, self.speed));
     }
 
     fn on_stop(&mut self, agent: Entity, world: &mut World, _reason: StopReason) {
@@ -30,27 +30,36 @@ impl Action for MoveAction {
 }
 
 #[derive(Component)]
-pub(crate) struct Move(Actor, ActorPosition, f32);
+pub(crate) struct Move(Actor, Vec<ActorPosition>, usize, f32);
 
 pub(crate) fn move_system(
     mut move_q: Query<(&mut Move, &mut ActionFinished)>,
     mut moveable: Query<(&Actor, &mut ActorPosition, &mut Transform)>,
     time: Res<Time>,
 ) {
-    for (target, mut finished) in move_q.iter_mut() {
+    for (mut target, mut finished) in move_q.iter_mut() {
+        let next = match target.1.get(target.2) {
+            Some(next) => *next,
+            None => {
+                finished.confirm_and_reset();
+                continue;
+            }
+        };
         for (actor, mut pos, mut transform) in moveable.iter_mut() {
             if actor == &target.0 {
                 let current_position = transform.translation;
-                let target_position =
-                    Vec3::new(target.1 .0 as f32, current_position.y, target.1 .1 as f32);
+                let target_position = Vec3::new(next.0 as f32, current_position.y, next.1 as f32);
                 let delta = target_position - transform.translation;
-                let distance_to_move = time.delta_seconds() * target.2;
+                let distance_to_move = time.delta_seconds() * target.3;
 
-                if delta.length_squared() <= distance_to_move {
+                if delta.length_squared() <= distance_to_move * distance_to_move {
                     transform.translation = target_position;
-                    pos.0 = target.1 .0;
-                    pos.1 = target.1 .1;
-                    finished.confirm_and_reset();
+                    pos.0 = next.0;
+                    pos.1 = next.1;
+                    target.2 += 1;
+                    if target.2 >= target.1.len() {
+                        finished.confirm_and_reset();
+                    }
                 } else {
                     let move_vector = delta.normalize() * distance_to_move;
                     transform.translation += move_vector;
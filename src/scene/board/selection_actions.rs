@@ -6,6 +6,8 @@ use bevy_mod_picking::NoDeselect;
 
 use bevy_mod_picking::Highlighting;
 
+use bevy_mod_picking::Hover;
+
 use bevy_mod_picking::PickableBundle;
 
 use bevy::prelude::*;
@@ -43,6 +45,9 @@ pub(crate) struct Selectable(usize, usize);
 #[derive(Component)]
 pub(crate) struct Selected;
 
+#[derive(Component)]
+pub(crate) struct Preview;
+
 pub(crate) fn setup_selectable(
     mut commands: Commands,
     board: Query<(Entity, &SelectTargets), Changed<SelectTargets>>,
@@ -109,11 +114,55 @@ pub(crate) fn process_selection_events(
     }
 }
 
+pub(crate) fn preview_affected_tiles(
+    mut commands: Commands,
+    mut events: EventReader<PickingEvent>,
+    board: Query<(Entity, &SelectTargets)>,
+    selectables: Query<(&Selectable, &Hover, Option<&Selected>)>,
+    hover_changed: Query<(), (With<Selectable>, Changed<Hover>)>,
+    newly_selected: Query<(), Added<Selected>>,
+    previews: Query<Entity, With<Preview>>,
+    assets: Res<BoardAssets>,
+) {
+    let picked = events.iter().count() > 0;
+    if !picked && hover_changed.is_empty() && newly_selected.is_empty() {
+        return;
+    }
+    let (board, select_targets) = match board.get_single() {
+        Ok(board) => board,
+        Err(_) => return,
+    };
+    for entity in previews.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let targets = selectables
+        .iter()
+        .filter(|(_, hover, selected)| hover.hovered() || selected.is_some())
+        .map(|(s, _, _)| (s.0, s.1))
+        .collect::<Vec<_>>();
+    let affected = select_targets.0.affected_by(&targets);
+    commands.entity(board).add_children(|parent| {
+        for pos in affected.iter() {
+            parent.spawn((
+                Preview,
+                PbrBundle {
+                    mesh: assets.selector.clone(),
+                    material: assets.selector_hover.clone(),
+                    transform: Transform::from_xyz(pos.0 as f32, 0.05, pos.1 as f32)
+                        .with_scale(Vec3::splat(0.8)),
+                    ..Default::default()
+                },
+            ));
+        }
+    });
+}
+
 pub(crate) fn set_selection(
     mut commands: Commands,
     mut board: Query<(Entity, &SelectTargets, &mut ActionFinished)>,
     selected: Query<(Entity, &Selectable), With<Selected>>,
     selectable: Query<Entity, With<Selectable>>,
+    previews: Query<Entity, With<Preview>>,
 ) {
     if let Ok((_board, select_targets, mut action_finished)) = board.get_single_mut() {
         let select = &select_targets.0;
@@ -128,7 +177,7 @@ pub(crate) fn set_selection(
                 select.action_id,
             );
             info!("Clearing Selectables");
-            for e in selectable.iter() {
+            for e in selectable.iter().chain(previews.iter()) {
                 commands.entity(e).despawn_recursive();
             }
             commands.insert_resource(process);
@@ -9,61 +9,57 @@ use bevy_sequential_actions::Action;
 
 use crate::scene::scenario::*;
 
-pub struct StunAction {
-    pub(crate) target: ActorPosition,
+pub struct StatusAction {
     pub(crate) actor: Actor,
     pub(crate) duration: f32,
-    pub(crate) stun_duration: usize,
+    pub(crate) impact: Impact,
 }
 
-impl Action for StunAction {
+impl Action for StatusAction {
     fn on_start(&mut self, agent: Entity, world: &mut World, _commands: &mut ActionCommands) {
         // Run the wait system on the agent
-        world.entity_mut(agent).insert(Stun(
+        world.entity_mut(agent).insert(ApplyStatus(
             self.actor,
-            self.target,
             self.duration,
             None,
-            self.stun_duration,
+            self.impact.clone(),
         ));
     }
 
     fn on_stop(&mut self, agent: Entity, world: &mut World, _reason: StopReason) {
-        // Restun the wait component from the agent
-        let _wait = world.entity_mut(agent).remove::<Stun>();
+        // Remove the status component from the agent
+        let _wait = world.entity_mut(agent).remove::<ApplyStatus>();
     }
 }
 
 #[derive(Component)]
-pub(crate) struct Stun(Actor, ActorPosition, f32, Option<f32>, usize);
+pub(crate) struct ApplyStatus(Actor, f32, Option<f32>, Impact);
 
-pub(crate) fn stun_system(
-    mut stun_q: Query<(&mut Stun, &mut ActionFinished)>,
-    mut actors: Query<(&Actor, &ActorPosition, &mut Transform)>,
+pub(crate) fn status_system(
+    mut status_q: Query<(&mut ApplyStatus, &mut ActionFinished)>,
+    mut actors: Query<(&Actor, &mut Transform)>,
     mut events: EventWriter<AdjustActorEvent>,
     time: Res<Time>,
 ) {
-    for (mut stun, mut finished) in stun_q.iter_mut() {
-        if stun.3.is_none() {
-            stun.3 = Some(time.elapsed_seconds());
+    for (mut status, mut finished) in status_q.iter_mut() {
+        if status.2.is_none() {
+            status.2 = Some(time.elapsed_seconds());
         }
-        if let Some(start) = stun.3 {
+        if let Some(start) = status.2 {
             let elapsed = time.elapsed_seconds() - start;
-            let complete = elapsed >= stun.2;
+            let complete = elapsed >= status.1;
             if complete {
                 finished.confirm_and_reset();
+                events.send_batch(status.3.actors.iter().cloned());
             }
-            for (actor, pos, mut transform) in actors.iter_mut() {
-                if actor == &stun.0 {
+            for (actor, mut transform) in actors.iter_mut() {
+                if actor == &status.0 {
                     if complete {
                         transform.scale = Vec3::splat(1.);
                     } else {
-                        transform.scale = Vec3::splat(1. + (elapsed / stun.2) * 0.5);
+                        transform.scale = Vec3::splat(1. + (elapsed / status.1) * 0.5);
                     }
                 }
-                if complete && *pos == stun.1 && actor != &stun.0 {
-                    events.send(AdjustActorEvent::Stun(*actor, stun.4));
-                }
             }
         }
     }
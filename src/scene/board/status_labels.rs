@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets,
+    scene::scenario::{Actor, ActorResources},
+};
+
+#[derive(Component)]
+pub(crate) struct StatusLabel(Actor);
+
+pub(crate) fn update_status_labels(
+    mut commands: Commands,
+    resources: Option<Res<ActorResources>>,
+    assets: Res<assets::Assets>,
+    labels: Query<Entity, With<StatusLabel>>,
+) {
+    let resources = match resources {
+        Some(resources) if resources.is_changed() => resources,
+        _ => return,
+    };
+    for entity in labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for actor in resources.turn_order.iter() {
+        let res = match resources.resources.get(actor) {
+            Some(res) if !res.statuses.is_empty() => res,
+            _ => continue,
+        };
+        let text = res
+            .statuses
+            .iter()
+            .map(|status| status.describe())
+            .collect::<Vec<_>>()
+            .join("\n");
+        commands.spawn((
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 14.,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            }),
+            StatusLabel(*actor),
+        ));
+    }
+}
+
+pub(crate) fn position_status_labels(
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    actors: Query<(&Actor, &GlobalTransform)>,
+    mut labels: Query<(&StatusLabel, &mut Style, &mut Visibility)>,
+) {
+    let (camera, camera_transform) = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    for (label, mut style, mut visibility) in labels.iter_mut() {
+        let screen_position = actors
+            .iter()
+            .find(|(actor, _)| **actor == label.0)
+            .and_then(|(_, transform)| {
+                camera.world_to_viewport(
+                    camera_transform,
+                    transform.translation() + Vec3::new(0.3, 0., 0.3),
+                )
+            });
+        match screen_position {
+            Some(position) => {
+                visibility.is_visible = true;
+                style.position = UiRect {
+                    left: Val::Px(position.x),
+                    bottom: Val::Px(position.y),
+                    ..Default::default()
+                };
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
+
+pub(crate) fn clear_status_labels(
+    mut commands: Commands,
+    labels: Query<Entity, With<StatusLabel>>,
+) {
+    for entity in labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy_sequential_actions::ActionFinished;
+
+use bevy_sequential_actions::StopReason;
+
+use bevy_sequential_actions::ActionCommands;
+
+use bevy_sequential_actions::Action;
+
+use crate::scene::scenario::*;
+
+pub struct TeleportAction {
+    pub(crate) target: ActorPosition,
+    pub(crate) actor: Actor,
+    pub(crate) duration: f32,
+}
+
+impl Action for TeleportAction {
+    fn on_start(&mut self, agent: Entity, world: &mut World, _commands: &mut ActionCommands) {
+        world
+            .entity_mut(agent)
+            .insert(Teleport(self.actor, self.target, self.duration, None));
+    }
+
+    fn on_stop(&mut self, agent: Entity, world: &mut World, _reason: StopReason) {
+        let _teleport = world.entity_mut(agent).remove::<Teleport>();
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct Teleport(Actor, ActorPosition, f32, Option<f32>);
+
+pub(crate) fn teleport_system(
+    mut teleport_q: Query<(&mut Teleport, &mut ActionFinished)>,
+    mut actors: Query<(&Actor, &mut ActorPosition, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (mut teleport, mut finished) in teleport_q.iter_mut() {
+        if teleport.3.is_none() {
+            teleport.3 = Some(time.elapsed_seconds());
+        }
+        if let Some(start) = teleport.3 {
+            let elapsed = time.elapsed_seconds() - start;
+            let half = teleport.2 / 2.;
+            let complete = elapsed >= teleport.2;
+            if complete {
+                finished.confirm_and_reset();
+            }
+            for (actor, mut pos, mut transform) in actors.iter_mut() {
+                if actor != &teleport.0 {
+                    continue;
+                }
+                if elapsed >= half && *pos != teleport.1 {
+                    *pos = teleport.1;
+                    transform.translation = Vec3::new(
+                        teleport.1 .0 as f32,
+                        transform.translation.y,
+                        teleport.1 .1 as f32,
+                    );
+                }
+                transform.scale = if complete {
+                    Vec3::splat(1.)
+                } else {
+                    Vec3::splat(((elapsed - half).abs() / half).clamp(0.05, 1.))
+                };
+            }
+        }
+    }
+}
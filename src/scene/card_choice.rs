@@ -0,0 +1,65 @@
+use crate::{
+    assets,
+    game_state::AppState,
+    scene::scenario::{card_flow, Actor, CardChoiceEvent, CurrentTurnProcess},
+    ui::*,
+};
+use bevy::prelude::*;
+
+pub struct CardChoicePlugin;
+
+impl Plugin for CardChoicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Scene)
+                .with_system(display_card_choice)
+                .with_system(click_event),
+        );
+    }
+}
+
+#[derive(Component)]
+struct CardChoiceMenu;
+
+fn display_card_choice(
+    mut commands: Commands,
+    assets: Res<assets::Assets>,
+    process: Option<Res<CurrentTurnProcess>>,
+) {
+    let process = match process {
+        Some(process) if process.is_changed() => process,
+        _ => return,
+    };
+    if let CurrentTurnProcess::CardChoice(Actor::Player, card, index) = process.as_ref() {
+        let options = card_flow::options(card, *index);
+        let menu = UiRoot::spawn(&mut commands, |parent| {
+            MainText::new(format!("{} - Choose one", card.name)).spawn(parent, &assets);
+            for (i, option) in options.iter().enumerate() {
+                MenuButton::Primary.spawn(format!("card-choice-{i}"), option, parent, &assets);
+            }
+        });
+        commands.entity(menu).insert(CardChoiceMenu);
+    }
+}
+
+fn click_event(
+    mut commands: Commands,
+    mut button_event: EventReader<ButtonClickEvent>,
+    mut choice: EventWriter<CardChoiceEvent>,
+    menus: Query<Entity, With<CardChoiceMenu>>,
+) {
+    for event in button_event.iter() {
+        let option = match event
+            .0
+            .strip_prefix("card-choice-")
+            .and_then(|option| option.parse::<usize>().ok())
+        {
+            Some(option) => option,
+            None => continue,
+        };
+        for menu in menus.iter() {
+            commands.entity(menu).despawn_recursive();
+        }
+        choice.send(CardChoiceEvent(option));
+    }
+}
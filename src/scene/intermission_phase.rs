@@ -15,24 +15,18 @@ impl Plugin for IntermissionPhasePlugin {
             SystemSet::on_enter(SceneState::Intermission)
                 .with_system(display_intermission_phase_menu),
         )
-        .add_system_set(
-            SystemSet::on_update(SceneState::Intermission)
-                .with_system(card_select)
-                .with_system(click_event),
-        )
+        .add_system_set(SystemSet::on_update(SceneState::Intermission).with_system(click_event))
         .add_system_set(clear_ui_system_set(SceneState::Intermission));
     }
 }
 
-#[derive(Component)]
-struct SelectedCounter(usize, usize);
-
 fn display_intermission_phase_menu(
     mut commands: Commands,
     scenario: Res<Scenario>,
     assets: Res<assets::Assets>,
     actor_resources: Res<ActorResources>,
     cards: Res<AvailableCards>,
+    card_collection: Res<Cards>,
 ) {
     info!("Displaying Intermission");
     UiRoot::spawn(&mut commands, |parent| {
@@ -69,26 +63,24 @@ fn display_intermission_phase_menu(
                             .spawn(parent, &assets);
                     }
 
-                    let available = {
-                        let used = if let Some(player_resource) =
-                            actor_resources.resources.get(&Actor::Player)
-                        {
-                            let mut hand = player_resource.hand.clone();
-                            let mut used = player_resource.used.clone();
-                            hand.append(&mut used);
-                            hand
-                        } else {
-                            vec![]
-                        };
-
-                        cards
-                            .cards
-                            .iter()
-                            .filter(|(id, _)| !used.contains(*id))
-                            .collect::<Vec<_>>()
-                    };
+                    let player_resource = actor_resources.resources.get(&Actor::Player);
+                    let owned = player_resource
+                        .map(|player_resource| {
+                            player_resource.owned_cards().cloned().collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    let available = cards
+                        .cards
+                        .iter()
+                        .filter(|(id, _)| !owned.contains(*id))
+                        .collect::<Vec<_>>();
+                    let upgrades = player_resource
+                        .map(|player_resource| {
+                            card_collection.upgrade_options(player_resource.owned_cards())
+                        })
+                        .unwrap_or_default();
 
-                    if available.is_empty() {
+                    if available.is_empty() && upgrades.is_empty() {
                         MainText::new("No cards available...")
                             .size(10.)
                             .spawn(parent, &assets);
@@ -102,69 +94,30 @@ fn display_intermission_phase_menu(
                     }
 
                     MainText::new("Choose one card to add to your hand:").spawn(parent, &assets);
-                    parent
-                        .spawn(NodeBundle {
-                            style: Style {
-                                flex_direction: FlexDirection::Row,
-                                justify_content: JustifyContent::Center,
-                                flex_wrap: FlexWrap::Wrap,
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        })
-                        .with_children(|parent| {
-                            for (_, card) in available.iter() {
-                                CardUI::card(card).selectable().spawn(parent, &assets);
-                            }
-                        });
-                    parent.spawn((NodeBundle::default(), SelectedCounter(0, 1)));
+                    CardUI::spawn_row(
+                        parent,
+                        &assets,
+                        available
+                            .iter()
+                            .map(|(_, card)| CardUI::card(card).selectable()),
+                    );
+                    if !upgrades.is_empty() {
+                        MainText::new("Or upgrade a card in your deck:").spawn(parent, &assets);
+                        CardUI::spawn_row(
+                            parent,
+                            &assets,
+                            upgrades.iter().map(|(base, upgraded)| {
+                                CardUI::upgrade(base, upgraded).selectable()
+                            }),
+                        );
+                    }
+                    SelectedCounter::new(1, "intermission-complete", "Continue Mission")
+                        .spawn(parent);
                 }
             });
     });
 }
 
-fn card_select(
-    mut events: EventReader<CardSelectedEvent>,
-    mut commands: Commands,
-    mut counters: Query<(Entity, &mut SelectedCounter)>,
-    assets: Res<assets::Assets>,
-) {
-    let mut added = 0;
-    let mut removed = 0;
-
-    for event in events.iter() {
-        if event.0 {
-            added += 1;
-        } else {
-            removed += 1;
-        }
-    }
-
-    for (entity, mut counter) in counters.iter_mut() {
-        counter.0 += added;
-        counter.0 = counter.0.checked_sub(removed).unwrap_or_default();
-
-        let selected = counter.0;
-        let total = counter.1;
-
-        commands.entity(entity).despawn_descendants();
-        commands.entity(entity).add_children(|parent| {
-            MainText::new(format!("Selected {selected}/{total} Cards"))
-                .size(10.)
-                .spawn(parent, &assets);
-
-            if selected == total {
-                MenuButton::Primary.spawn(
-                    "intermission-complete",
-                    "Continue Mission",
-                    parent,
-                    &assets,
-                );
-            }
-        });
-    }
-}
-
 fn click_event(
     mut commands: Commands,
     mut events: EventReader<ButtonClickEvent>,
@@ -175,20 +128,19 @@ fn click_event(
 ) {
     for event in events.iter() {
         if event.0 == "intermission-complete" {
-            let mut selected = cards
-                .iter()
-                .filter_map(|card| {
-                    if card.selected {
-                        Some(card.card_id.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
             if let Some(player_resource) = actor_resources.resources.get_mut(&Actor::Player) {
-                player_resource.hand.append(&mut player_resource.used);
-                player_resource.hand.append(&mut selected);
+                let mut selected = vec![];
+                for card in cards.iter().filter(|card| card.selected) {
+                    match &card.upgrades {
+                        Some(base) => {
+                            if !player_resource.upgrade_card(base, &card.card_id) {
+                                warn!("Couldn't find {base} to upgrade");
+                            }
+                        }
+                        None => selected.push(card.card_id.clone()),
+                    }
+                }
+                player_resource.add_to_deck(selected);
             }
             let _ = scene_state.set(SceneState::Processing);
             commands.insert_resource(CurrentTurnProcess::Thinking(Actor::Player));
@@ -1,7 +1,14 @@
 use crate::{
     assets,
     card::*,
-    scene::{scenario::*, SceneState},
+    save::SuspendBattleEvent,
+    scene::{
+        scenario::{
+            simulation::{self, TurnStart},
+            *,
+        },
+        SceneState,
+    },
     story::{Scenario, ScenarioState},
     ui::*,
 };
@@ -22,11 +29,16 @@ impl Plugin for PlayerTurnPlugin {
 fn display_playerturn_phase_menu(
     mut commands: Commands,
     assets: Res<assets::Assets>,
-    cards: Res<AvailableCards>,
+    cards: Res<Cards>,
     mut selected_cards: ResMut<ActorResources>,
     scenario: Res<Scenario>,
+    process: Res<CurrentTurnProcess>,
 ) {
-    let player_resource = selected_cards.resources.get_mut(&Actor::Player).unwrap();
+    let turn_start = match *process {
+        CurrentTurnProcess::CardResolved(Actor::Player) => TurnStart::Ready,
+        _ => simulation::start_turn(&mut selected_cards, &Actor::Player),
+    };
+    let player_resource = selected_cards.resources.get(&Actor::Player).unwrap();
     UiRoot::spawn(&mut commands, |parent| {
         parent
             .spawn(NodeBundle {
@@ -49,12 +61,13 @@ fn display_playerturn_phase_menu(
                             .spawn(parent, &assets);
                     }
                 }
-                if player_resource.stun_duration > 0 {
-                    player_resource.stun_duration = player_resource
-                        .stun_duration
-                        .checked_sub(1)
-                        .unwrap_or_default();
-                    MainText::new("You are stunned!").spawn(parent, &assets);
+                let skipped = match turn_start {
+                    TurnStart::Ready => None,
+                    TurnStart::Stunned => Some("You are stunned!"),
+                    TurnStart::Defeated => Some("You have succumbed to your wounds..."),
+                };
+                if let Some(message) = skipped {
+                    MainText::new(message).spawn(parent, &assets);
                     MenuButton::Primary.spawn("stunned-continue", "Continue...", parent, &assets);
                     return;
                 }
@@ -81,6 +94,35 @@ fn display_playerturn_phase_menu(
                 ))
                 .size(15.)
                 .spawn(parent, &assets);
+                MainText::new(format!(
+                    "Energy: {}/{}",
+                    player_resource.energy, player_resource.max_energy
+                ))
+                .size(15.)
+                .spawn(parent, &assets);
+                MainText::new(format!(
+                    "Draw pile: {} | Discard pile: {} | Exhausted: {}",
+                    player_resource.draw_pile.len(),
+                    player_resource.discarded.len(),
+                    player_resource.exhausted.len()
+                ))
+                .size(15.)
+                .spawn(parent, &assets);
+                if !player_resource.statuses.is_empty() {
+                    MainText::new(format!(
+                        "Statuses: {}",
+                        player_resource
+                            .statuses
+                            .iter()
+                            .map(|status| status.describe())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                    .size(15.)
+                    .spawn(parent, &assets);
+                }
+                MenuButton::Primary.spawn("end-turn", "End Turn", parent, &assets);
+                MenuButton::Primary.spawn("suspend-battle", "Suspend Battle", parent, &assets);
             });
     });
 }
@@ -89,9 +131,21 @@ fn click_event(
     mut events: EventReader<CardClickEvent>,
     mut sender: EventWriter<CardPlayedEvent>,
     mut button_event: EventReader<ButtonClickEvent>,
+    mut suspend: EventWriter<SuspendBattleEvent>,
     mut commands: Commands,
+    resources: Res<ActorResources>,
+    cards: Res<Cards>,
 ) {
     for event in events.iter() {
+        let playable = cards
+            .cards
+            .get(&event.0)
+            .map(|card| simulation::can_play(&resources, &Actor::Player, card))
+            .unwrap_or(false);
+        if !playable {
+            info!("Not enough energy to play {}", event.0);
+            continue;
+        }
         let played = CardPlayedEvent {
             actor: Actor::Player,
             card: event.0.to_string(),
@@ -100,8 +154,10 @@ fn click_event(
         sender.send(played);
     }
     for event in button_event.iter() {
-        if event.0 == "stunned-continue" {
+        if event.0 == "stunned-continue" || event.0 == "end-turn" {
             commands.insert_resource(CurrentTurnProcess::Done(Actor::Player));
+        } else if event.0 == "suspend-battle" {
+            suspend.send(SuspendBattleEvent);
         };
     }
 }
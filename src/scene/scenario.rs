@@ -1,16 +1,23 @@
-mod enemy_ai;
+pub mod card_flow;
+pub mod deck;
+pub mod enemy_ai;
+pub mod pathfinding;
 pub mod scenario_map;
 mod scenario_utilities;
+pub mod simulation;
+pub mod status;
 pub mod types;
 
 use bevy::prelude::*;
-use bevy_turborand::{DelegatedRng, GlobalRng};
 
 use crate::{
-    card::Cards,
+    card::{Card, Cards},
+    enemy::Enemies,
     game_state::AppState,
+    map::Maps,
+    run_seed::RunRngs,
     scene::SceneState,
-    story::{Scenario, ScenarioState},
+    story::{Scenario, ScenarioState, Story},
 };
 
 pub use scenario_map::*;
@@ -21,8 +28,10 @@ pub struct ScenarioPlugin;
 impl Plugin for ScenarioPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_event::<CardPlayedEvent>()
+            .add_event::<CardChoiceEvent>()
             .add_event::<AnimateActionsEvents>()
             .add_event::<AdjustActorEvent>()
+            .add_event::<AdjustTileEvent>()
             .init_resource::<ActorResources>()
             .insert_resource(CurrentTurnProcess::None)
             .add_system_set(SystemSet::on_enter(SceneState::Setup).with_system(setup_scenario))
@@ -35,8 +44,10 @@ impl Plugin for ScenarioPlugin {
                     .with_system(process_card_events)
                     .with_system(next_turn_ready)
                     .with_system(process_card_action)
+                    .with_system(process_card_choice)
                     .with_system(apply_action_to_targets)
-                    .with_system(apply_effects_to_actors),
+                    .with_system(apply_effects_to_actors)
+                    .with_system(apply_effects_to_tiles),
             );
     }
 }
@@ -44,52 +55,29 @@ impl Plugin for ScenarioPlugin {
 fn setup_scenario(
     mut commands: Commands,
     cards: Res<Cards>,
-    mut global_rng: ResMut<GlobalRng>,
+    enemies: Res<Enemies>,
+    maps: Res<Maps>,
+    story: Option<Res<Story>>,
+    mut rngs: ResMut<RunRngs>,
     current_scenario: Option<Res<Scenario>>,
 ) {
     if let Some(scenario) = current_scenario {
-        let cards = cards.cards.iter().collect::<Vec<_>>();
-        let num_cards = cards.len();
-
-        let mut selected = Vec::with_capacity(3);
-
-        for _i in 0..3 {
-            let mut next = global_rng.usize(0..num_cards);
-            while selected.contains(&next) {
-                next = global_rng.usize(0..num_cards);
-            }
-            selected.push(next);
-        }
-
-        let selected = selected
-            .iter()
-            .filter_map(|i| cards.get(*i))
-            .map(|(id, _)| id.to_string())
-            .collect::<Vec<_>>();
-
-        let resources = (0..global_rng.usize(3..5))
-            .map(|i| {
-                (
-                    Actor::Enemy(i),
-                    ActorResource {
-                        hand: selected.clone(),
-                        health: 2,
-                        max_health: 2,
-                        ..Default::default()
-                    },
-                )
-            })
-            .collect();
+        let tier = story.map(|story| story.phase.enemy_tier()).unwrap_or(0);
+        let resources = simulation::generate_enemies(
+            &mut rngs.enemy_hands,
+            &enemies.enemies,
+            &cards.cards,
+            tier,
+        );
 
-        let map =
-            scenario_map::ScenarioMap::generate(global_rng.as_mut(), scenario.as_ref(), &resources);
+        let map = scenario_map::ScenarioMap::generate(
+            &mut rngs.map,
+            scenario.as_ref(),
+            &resources,
+            &maps.maps,
+        );
 
-        let mut turn_order = resources
-            .iter()
-            .map(|(actor, _)| *actor)
-            .collect::<Vec<_>>();
-        turn_order.push(Actor::Player);
-        turn_order.sort();
+        let turn_order = simulation::turn_order(&resources);
 
         commands.insert_resource(ActorResources {
             resources,
@@ -107,35 +95,37 @@ fn process_card_events(
     mut scene_state: ResMut<State<SceneState>>,
     mut animate: EventWriter<AnimateActionsEvents>,
     mut commands: Commands,
+    position_query: Query<(&Actor, &ActorPosition)>,
 ) {
     for CardPlayedEvent { actor, card } in events.iter() {
         info!("Setting state to processing");
         let _ = scene_state.overwrite_set(SceneState::Processing);
         if let Some(resources) = resources.as_mut() {
-            // Check Hand
-            if let Some(actor_resources) = resources.resources.get_mut(actor) {
-                if actor_resources.hand.contains(card) {
-                    actor_resources.hand.retain(|c| c != card);
-                    actor_resources.used.push(card.clone());
-
-                    if actor_resources.hand.is_empty() {
-                        actor_resources.hand = actor_resources.used.clone();
-                        actor_resources.used = vec![];
-                    }
-
-                    if let Some(card) = cards.cards.get(card) {
-                        commands.insert_resource(CurrentTurnProcess::CardActionTriggered(
-                            *actor,
-                            card.clone(),
-                            0,
-                        ));
-                        bevy::log::info!("Triggered Card Action");
-                        return;
-                    }
+            if let Some(card) = cards.cards.get(card) {
+                if simulation::play_card(resources, actor, card) {
+                    let positions = position_query
+                        .iter()
+                        .map(|(a, p)| (*a, *p))
+                        .collect::<Vec<_>>();
+                    let mut card = card.clone();
+                    let step = card_flow::advance(
+                        &mut card,
+                        0,
+                        &card_flow::FlowContext {
+                            actor: *actor,
+                            positions: &positions,
+                            resources,
+                            targets: &[],
+                        },
+                    );
+                    commands.insert_resource(step_process(*actor, card, step));
+                    bevy::log::info!("Triggered Card Action");
+                    return;
                 }
             }
 
-            queue_next_turn(&mut commands, resources, actor, &mut animate);
+            info!("Card can't be played");
+            commands.insert_resource(CurrentTurnProcess::CardResolved(*actor));
         } else {
             commands.insert_resource(CurrentTurnProcess::Thinking(Actor::Player));
             animate.send(AnimateActionsEvents::Wait(0.2));
@@ -166,100 +156,161 @@ fn next_turn_ready(
     let mut resources = resources.unwrap();
     let mut scenario = scenario.unwrap();
 
-    if let CurrentTurnProcess::Done(actor) = *current_turn_process {
-        let _ = scene_state.set(SceneState::Processing);
-        let positions = position_query.iter().collect::<Vec<_>>();
+    let (actor, turn_over) = match *current_turn_process {
+        CurrentTurnProcess::Done(actor) => (actor, true),
+        CurrentTurnProcess::CardResolved(actor) => (actor, !resources.turn_order.contains(&actor)),
+        _ => return,
+    };
+    let _ = scene_state.set(SceneState::Processing);
+    let positions = position_query
+        .iter()
+        .map(|(a, p)| (*a, *p))
+        .collect::<Vec<_>>();
+
+    if turn_over {
+        if let Some(effect) = simulation::terrain_effect(map.as_ref(), &positions, &actor) {
+            info!("Terrain effect {:?}", effect);
+            simulation::apply_adjustment(&mut resources, &effect);
+        }
+        simulation::end_turn(&mut resources, &actor);
+    }
 
-        let mut current_goal_id = 0;
-        for goal in goal_query.iter() {
-            if let GoalStatus::Active = goal.status {
-                current_goal_id = goal.number;
-                break;
-            }
+    let mut current_goal_id = 0;
+    for goal in goal_query.iter() {
+        if let GoalStatus::Active = goal.status {
+            current_goal_id = goal.number;
+            break;
         }
+    }
 
-        let goal_success = check_current_goal(
-            &current_goal_id,
-            scenario.as_ref(),
-            map.as_ref(),
-            &positions,
-            resources.as_ref(),
-        );
-        if goal_success {
-            info!("Goal Succeeded");
-            let state = scenario.succeed();
-            info!("Scenario State {:?}", state);
-
-            for mut goal in goal_query.iter_mut() {
-                if let GoalStatus::Active = goal.status {
-                    goal.status = GoalStatus::Completed;
-                    break;
-                }
-            }
+    let goal_success =
+        simulation::goal_reached(scenario.as_ref(), current_goal_id, map.as_ref(), &positions);
+    if goal_success {
+        info!("Goal Succeeded");
+        let state = scenario.succeed();
+        info!("Scenario State {:?}", state);
 
-            let next_goal = current_goal_id + 1;
-            for mut goal in goal_query.iter_mut() {
-                if goal.number == next_goal {
-                    goal.status = GoalStatus::Active;
-                    break;
-                }
+        for mut goal in goal_query.iter_mut() {
+            if let GoalStatus::Active = goal.status {
+                goal.status = GoalStatus::Completed;
+                break;
             }
+        }
 
-            if let ScenarioState::Success(_) = state {
-                info!("Scenario Complete");
-                let _ = scene_state.overwrite_set(SceneState::Succeeded);
-                return;
-            } else {
-                let _ = scene_state.overwrite_set(SceneState::Intermission);
-                return;
+        let next_goal = current_goal_id + 1;
+        for mut goal in goal_query.iter_mut() {
+            if goal.number == next_goal {
+                goal.status = GoalStatus::Active;
+                break;
             }
         }
 
-        let failure = check_player_failed(&resources);
-        if failure {
-            info!("Failed...");
-            scenario.fail();
-            let _ = scene_state.overwrite_set(SceneState::Failed);
+        if let ScenarioState::Success(_) = state {
+            info!("Scenario Complete");
+            let _ = scene_state.overwrite_set(SceneState::Succeeded);
+            return;
+        } else {
+            let _ = scene_state.overwrite_set(SceneState::Intermission);
             return;
         }
+    }
 
-        queue_next_turn(&mut commands, &mut resources, &actor, &mut animate);
+    let failure = simulation::player_failed(&resources);
+    if failure {
+        info!("Failed...");
+        scenario.fail();
+        let _ = scene_state.overwrite_set(SceneState::Failed);
+        return;
     }
+
+    if turn_over {
+        queue_next_turn(&mut commands, &resources, &actor, &mut animate);
+    } else {
+        info!("Turn continues for {:?}", actor);
+        animate.send(AnimateActionsEvents::Wait(0.2));
+        animate.send(AnimateActionsEvents::Continue(actor));
+    }
+}
+
+fn step_process(actor: Actor, card: Card, step: card_flow::NextStep) -> CurrentTurnProcess {
+    match step {
+        card_flow::NextStep::Perform(index) => {
+            CurrentTurnProcess::CardActionTriggered(actor, card, index)
+        }
+        card_flow::NextStep::Choose(index) => CurrentTurnProcess::CardChoice(actor, card, index),
+        card_flow::NextStep::Done => CurrentTurnProcess::CardResolved(actor),
+    }
+}
+
+fn process_card_choice(
+    current_turn_process: Res<CurrentTurnProcess>,
+    mut commands: Commands,
+    mut events: EventReader<CardChoiceEvent>,
+    resources: Option<Res<ActorResources>>,
+    map: Option<Res<ScenarioMap>>,
+    position_query: Query<(&Actor, &ActorPosition)>,
+    mut rngs: ResMut<RunRngs>,
+    cards: Res<Cards>,
+    enemies: Res<Enemies>,
+    behaviours: Query<(&Actor, &enemy_ai::EnemyBehaviour)>,
+) {
+    let (actor, card, index) = match &*current_turn_process {
+        CurrentTurnProcess::CardChoice(actor, card, index) => (actor, card, *index),
+        _ => return,
+    };
+    let (resources, map) = match (resources, map) {
+        (Some(resources), Some(map)) => (resources, map),
+        _ => return,
+    };
+    let positions = position_query
+        .iter()
+        .map(|(a, p)| (*a, *p))
+        .collect::<Vec<_>>();
+    let option = match actor {
+        Actor::Player => match events.iter().last() {
+            Some(CardChoiceEvent(option)) => *option,
+            None => return,
+        },
+        Actor::Enemy(_) if current_turn_process.is_changed() => {
+            let view = enemy_ai::BattleView {
+                map: &map,
+                positions: &positions,
+                resources: resources.as_ref(),
+                cards: &cards.cards,
+                enemies: &enemies.enemies,
+            };
+            enemy_ai::with_policy(&behaviours, resources.as_ref(), actor, |policy| {
+                policy.choose_option(&mut rngs.enemy_ai, &view, actor, card, index)
+            })
+        }
+        Actor::Enemy(_) => return,
+    };
+    info!("Option {} chosen", option);
+    let mut card = card.clone();
+    card_flow::choose(&mut card, index, option);
+    let step = card_flow::advance(
+        &mut card,
+        index,
+        &card_flow::FlowContext {
+            actor: *actor,
+            positions: &positions,
+            resources: resources.as_ref(),
+            targets: &[],
+        },
+    );
+    commands.insert_resource(step_process(*actor, card, step));
 }
 
 fn queue_next_turn(
     commands: &mut Commands,
-    resources: &mut ResMut<ActorResources>,
+    resources: &ActorResources,
     actor: &Actor,
     animate: &mut EventWriter<AnimateActionsEvents>,
 ) {
-    if let Some(current_turn) =
-        resources.turn_order.iter().enumerate().find_map(
-            |(id, a)| {
-                if a == actor {
-                    Some(id)
-                } else {
-                    None
-                }
-            },
-        )
-    {
-        let next_turn = current_turn + 1;
-        let next_turn = if next_turn >= resources.turn_order.len() {
-            0
-        } else {
-            next_turn
-        };
-        if let Some(next_actor) = resources.turn_order.get(next_turn) {
-            commands.insert_resource(CurrentTurnProcess::Thinking(*next_actor));
-            animate.send(AnimateActionsEvents::Wait(0.2));
-            animate.send(AnimateActionsEvents::Continue(*next_actor));
-        }
-    } else {
-        commands.insert_resource(CurrentTurnProcess::Thinking(Actor::Player));
-        animate.send(AnimateActionsEvents::Wait(0.2));
-        animate.send(AnimateActionsEvents::Continue(Actor::Player));
-    }
+    let next_actor = simulation::next_actor(resources, actor);
+    commands.insert_resource(CurrentTurnProcess::Thinking(next_actor));
+    animate.send(AnimateActionsEvents::Wait(0.2));
+    animate.send(AnimateActionsEvents::Continue(next_actor));
 }
 
 fn process_card_action(
@@ -269,7 +320,9 @@ fn process_card_action(
     map: Option<Res<scenario_map::ScenarioMap>>,
     position_query: Query<(&Actor, &ActorPosition)>,
     mut animate: EventWriter<AnimateActionsEvents>,
-    mut global_rng: ResMut<GlobalRng>,
+    mut rngs: ResMut<RunRngs>,
+    cards: Res<Cards>,
+    enemies: Res<Enemies>,
 ) {
     if !current_turn_process.is_changed() {
         return;
@@ -280,23 +333,20 @@ fn process_card_action(
     {
         bevy::log::info!("Processing Card Action");
         if let (Some(resources), Some(map)) = (resources, map) {
-            if let Some(current_action) = card.actions.get(*action_index) {
-                let targetable = current_action.target();
-
-                let positions = position_query
-                    .iter()
-                    .map(|(a, p)| (*a, *p))
-                    .collect::<Vec<_>>();
-
-                let valid_targets = scenario_utilities::propose_valid_targets(
-                    actor,
-                    &targetable,
-                    &positions,
-                    &map,
-                    resources.as_ref(),
-                );
-
-                if valid_targets.is_empty() {
+            let positions = position_query
+                .iter()
+                .map(|(a, p)| (*a, *p))
+                .collect::<Vec<_>>();
+
+            if let Some(target_selection) = scenario_utilities::target_selection(
+                actor,
+                card,
+                *action_index,
+                &positions,
+                &map,
+                resources.as_ref(),
+            ) {
+                if target_selection.valid_targets.is_empty() {
                     commands.insert_resource(CurrentTurnProcess::CardTargetsSelected(
                         *actor,
                         card.clone(),
@@ -306,14 +356,6 @@ fn process_card_action(
                     return;
                 }
 
-                let target_selection = TargetSelection {
-                    actor: *actor,
-                    card: card.clone(),
-                    valid_targets,
-                    num_targets_to_select: targetable.num_targets(),
-                    action_id: *action_index,
-                };
-
                 match actor {
                     Actor::Player => {
                         bevy::log::info!("Setting Up Valid Player Targets");
@@ -321,13 +363,21 @@ fn process_card_action(
                     }
                     Actor::Enemy(_) => {
                         bevy::log::info!("Selecting Enemy Targets");
-                        let targets =
-                            enemy_ai::select_target(global_rng.as_mut(), &target_selection);
+                        let view = enemy_ai::BattleView {
+                            map: &map,
+                            positions: &positions,
+                            resources: resources.as_ref(),
+                            cards: &cards.cards,
+                            enemies: &enemies.enemies,
+                        };
+                        let targets = enemy_ai::policy_for(resources.as_ref(), actor)
+                            .select_targets(&mut rngs.enemy_ai, &view, &target_selection);
                         info!("Enemy Targets Selected");
                         let TargetSelection {
                             actor,
                             card,
                             valid_targets: _,
+                            affected_tiles: _,
                             num_targets_to_select: _,
                             action_id,
                         } = target_selection;
@@ -337,7 +387,7 @@ fn process_card_action(
                     }
                 }
             } else {
-                commands.insert_resource(CurrentTurnProcess::Done(*actor));
+                commands.insert_resource(CurrentTurnProcess::CardResolved(*actor));
             }
         }
     }
@@ -346,6 +396,11 @@ fn process_card_action(
 fn apply_action_to_targets(
     current_turn_process: Res<CurrentTurnProcess>,
     mut animate: EventWriter<AnimateActionsEvents>,
+    map: Option<Res<scenario_map::ScenarioMap>>,
+    resources: Option<Res<ActorResources>>,
+    cards: Res<Cards>,
+    enemies: Res<Enemies>,
+    position_query: Query<(&Actor, &ActorPosition)>,
 ) {
     if !current_turn_process.is_changed() {
         return;
@@ -354,50 +409,56 @@ fn apply_action_to_targets(
     if let CurrentTurnProcess::CardTargetsSelected(actor, card, targets, action_index) =
         &*current_turn_process
     {
+        let (map, resources) = match (map, resources) {
+            (Some(map), Some(resources)) => (map, resources),
+            _ => return,
+        };
+        // Resolve against a copy - the board itself only changes as each animation lands
+        let mut map = map.as_ref().clone();
+        let mut resources = resources.as_ref().clone();
+        let mut positions = position_query
+            .iter()
+            .map(|(a, p)| (*a, *p))
+            .collect::<Vec<_>>();
         if let Some(action) = card.actions.get(*action_index) {
-            match action {
-                crate::card::CardAction::Move(_) => {
-                    if let Some(position) = targets.first() {
-                        animate.send(AnimateActionsEvents::Move(
-                            *actor,
-                            ActorPosition(position.0, position.1),
-                        ));
-                    }
-                }
-                crate::card::CardAction::Attack { damage, range: _ } => {
-                    if let Some(position) = targets.first() {
-                        animate.send(AnimateActionsEvents::Attack(
-                            *actor,
-                            ActorPosition(position.0, position.1),
-                            *damage,
-                        ));
-                    }
-                }
-                crate::card::CardAction::Stun { range: _, duration } => {
-                    if let Some(position) = targets.first() {
-                        animate.send(AnimateActionsEvents::Stun(
-                            *actor,
-                            ActorPosition(position.0, position.1),
-                            *duration,
-                        ));
-                    }
-                }
+            let resolved = simulation::resolve_action(
+                actor,
+                action,
+                targets,
+                &mut map,
+                &enemies.enemies,
+                &cards.cards,
+                &mut positions,
+                &mut resources,
+            );
+            for event in resolved {
+                animate.send(event);
             }
         }
 
-        let next_action = action_index + 1;
+        let mut card = card.clone();
+        let step = match resources.as_ref() {
+            Some(resources) => card_flow::advance(
+                &mut card,
+                action_index + 1,
+                &card_flow::FlowContext {
+                    actor: *actor,
+                    positions: &positions,
+                    resources,
+                    targets,
+                },
+            ),
+            None => card_flow::NextStep::Done,
+        };
 
-        if card.actions.len() <= next_action {
+        if step == card_flow::NextStep::Done {
             info!("Turn Complete - schedule done");
-            animate.send(AnimateActionsEvents::SetTurnProcess(
-                CurrentTurnProcess::Done(*actor),
-            ));
         } else {
             info!("Turn Continues - schedule next action");
-            animate.send(AnimateActionsEvents::SetTurnProcess(
-                CurrentTurnProcess::CardActionTriggered(*actor, card.clone(), next_action),
-            ));
         }
+        animate.send(AnimateActionsEvents::SetTurnProcess(step_process(
+            *actor, card, step,
+        )));
     }
 }
 
@@ -407,79 +468,26 @@ fn apply_effects_to_actors(
 ) {
     if let Some(mut resources) = resources {
         for event in events.iter() {
-            match event {
-                AdjustActorEvent::Damage(actor, damage) => {
-                    if let Some(mut res) = resources.resources.get_mut(actor) {
-                        res.health = res.health.checked_sub(*damage).unwrap_or_default();
-                        info!("{} Damage Applied to {:?}", *damage, &actor);
-                        if res.health == 0 {
-                            info!("{:?} is Dead!", &actor);
-                            resources.turn_order = resources
-                                .turn_order
-                                .iter()
-                                .filter_map(|a| if a != actor { Some(*a) } else { None })
-                                .collect()
-                        }
-                    }
-                }
-                AdjustActorEvent::Stun(actor, duration) => {
-                    if let Some(mut res) = resources.resources.get_mut(actor) {
-                        if res.stun_duration < *duration {
-                            res.stun_duration = *duration;
-                        }
-                    }
-                }
-            }
+            simulation::apply_adjustment(&mut resources, event);
         }
     }
 }
 
-fn current_turn_process_changed(p: Option<Res<CurrentTurnProcess>>) {
-    if let Some(p) = p {
-        if p.is_changed() {
-            info!("Process Changed: {:?}", p);
-        }
-    }
-}
-
-fn check_current_goal(
-    current_goal_id: &usize,
-    scenario: &Scenario,
-    scenario_map: &ScenarioMap,
-    positions: &[(&Actor, &ActorPosition)],
-    _resources: &ActorResources,
-) -> bool {
-    if let Some(goal) = scenario.goals.get(*current_goal_id) {
-        match goal.goal_type {
-            crate::story::GoalType::ReachLocation(_) => {
-                for tile in scenario_map.tiles.iter() {
-                    match tile.tag {
-                        TileTag::Target(i) if i == *current_goal_id => {
-                            let pos = tile.pos;
-                            let player_pos = positions.iter().find_map(|(a, p)| match a {
-                                Actor::Player => Some((p.0, p.1)),
-                                Actor::Enemy(_) => None,
-                            });
-                            info!("Checking player success {:?} - {:?}", pos, player_pos);
-                            if Some(pos) == player_pos {
-                                return true;
-                            }
-                            break;
-                        }
-                        _ => {}
-                    }
-                }
-            }
+fn apply_effects_to_tiles(
+    map: Option<ResMut<ScenarioMap>>,
+    mut events: EventReader<AdjustTileEvent>,
+) {
+    if let Some(mut map) = map {
+        for event in events.iter() {
+            simulation::apply_tile_adjustment(&mut map, event);
         }
     }
-    false
 }
 
-fn check_player_failed(resources: &ActorResources) -> bool {
-    if let Some(player_resource) = resources.resources.get(&Actor::Player) {
-        if player_resource.health == 0 {
-            return true;
+fn current_turn_process_changed(p: Option<Res<CurrentTurnProcess>>) {
+    if let Some(p) = p {
+        if p.is_changed() {
+            info!("Process Changed: {:?}", p);
         }
     }
-    false
 }
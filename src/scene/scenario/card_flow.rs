@@ -0,0 +1,345 @@
+use crate::card::{describe_actions, Bonus, Card, CardAction, Condition, Count};
+
+use super::{enemy_ai, types::*};
+
+const MAX_REPEATS: usize = 8;
+
+pub struct FlowContext<'a> {
+    pub actor: Actor,
+    pub positions: &'a [(Actor, ActorPosition)],
+    pub resources: &'a ActorResources,
+    pub targets: &'a [(usize, usize)],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextStep {
+    Perform(usize),
+    Choose(usize),
+    Done,
+}
+
+impl<'a> FlowContext<'a> {
+    pub fn with_targets(&self, targets: &'a [(usize, usize)]) -> Self {
+        Self {
+            actor: self.actor,
+            positions: self.positions,
+            resources: self.resources,
+            targets,
+        }
+    }
+
+    fn caster(&self) -> Option<&ActorResource> {
+        self.resources.resources.get(&self.actor)
+    }
+
+    fn target_resources(&self) -> Vec<&ActorResource> {
+        self.positions
+            .iter()
+            .filter(|(_, p)| self.targets.contains(&p.into()))
+            .filter_map(|(a, _)| self.resources.resources.get(a))
+            .collect()
+    }
+
+    fn hostile(&self, other: &Actor) -> bool {
+        matches!(
+            (self.actor, other),
+            (Actor::Player, Actor::Enemy(_)) | (Actor::Enemy(_), Actor::Player)
+        )
+    }
+
+    fn count_within(&self, range: usize, hostile: bool) -> usize {
+        let origin = match enemy_ai::position_of(self.positions, &self.actor) {
+            Some(origin) => origin,
+            None => return 0,
+        };
+        self.positions
+            .iter()
+            .filter(|(a, p)| {
+                *a != self.actor
+                    && self.hostile(a) == hostile
+                    && self.resources.turn_order.contains(a)
+                    && enemy_ai::distance(origin, p.into()) <= range
+            })
+            .count()
+    }
+}
+
+impl Count {
+    pub fn evaluate(&self, ctx: &FlowContext) -> usize {
+        match self {
+            Count::Fixed(count) => *count,
+            Count::EnemiesWithin(range) => ctx.count_within(*range, true),
+            Count::AlliesWithin(range) => ctx.count_within(*range, false),
+            Count::CardsInHand => ctx.caster().map(|res| res.hand.len()).unwrap_or(0),
+        }
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, ctx: &FlowContext) -> bool {
+        match self {
+            Condition::TargetHas(kind) => ctx
+                .target_resources()
+                .iter()
+                .any(|res| res.has_status(*kind)),
+            Condition::CasterHas(kind) => ctx
+                .caster()
+                .map(|res| res.has_status(*kind))
+                .unwrap_or(false),
+            Condition::TargetHealthAtMost(health) => ctx
+                .target_resources()
+                .iter()
+                .any(|res| res.health <= *health),
+            Condition::CasterHealthAtMost(health) => ctx
+                .caster()
+                .map(|res| res.health <= *health)
+                .unwrap_or(false),
+            Condition::AtLeast(count, value) => count.evaluate(ctx) >= *value,
+            Condition::Not(condition) => !condition.holds(ctx),
+            Condition::All(conditions) => conditions.iter().all(|c| c.holds(ctx)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.holds(ctx)),
+        }
+    }
+}
+
+pub fn bonus_damage(bonus: &[Bonus], ctx: &FlowContext) -> usize {
+    bonus
+        .iter()
+        .filter(|b| b.when.holds(ctx))
+        .map(|b| b.damage)
+        .sum()
+}
+
+pub fn advance(card: &mut Card, index: usize, ctx: &FlowContext) -> NextStep {
+    loop {
+        let expansion = match card.actions.get(index) {
+            None => return NextStep::Done,
+            Some(CardAction::If {
+                condition,
+                then,
+                otherwise,
+            }) => {
+                if condition.holds(ctx) {
+                    then.clone()
+                } else {
+                    otherwise.clone()
+                }
+            }
+            Some(CardAction::Repeat { times, actions }) => {
+                let times = times.evaluate(ctx).min(MAX_REPEATS);
+                std::iter::repeat(actions)
+                    .take(times)
+                    .flatten()
+                    .cloned()
+                    .collect()
+            }
+            Some(CardAction::Choose(options)) if options.len() <= 1 => {
+                options.first().cloned().unwrap_or_default()
+            }
+            Some(CardAction::Choose(_)) => return NextStep::Choose(index),
+            Some(_) => return NextStep::Perform(index),
+        };
+        card.actions.splice(index..=index, expansion);
+    }
+}
+
+pub fn choose(card: &mut Card, index: usize, option: usize) {
+    let chosen = match card.actions.get(index) {
+        Some(CardAction::Choose(options)) => options.get(option).cloned().unwrap_or_default(),
+        _ => return,
+    };
+    card.actions.splice(index..=index, chosen);
+}
+
+pub fn options(card: &Card, index: usize) -> Vec<String> {
+    match card.actions.get(index) {
+        Some(CardAction::Choose(options)) => options
+            .iter()
+            .map(|option| describe_actions(option))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashMap;
+
+    use super::*;
+    use crate::scene::scenario::status::{Status, StatusKind};
+
+    fn card(yaml: &str) -> Card {
+        serde_yaml::from_str(&format!("id: test\nname: Test\ntier: 0\nactions:\n{yaml}")).unwrap()
+    }
+
+    // The player stands at (2, 2) with two enemies next to it and one far away
+    fn board(player_health: usize) -> (Vec<(Actor, ActorPosition)>, ActorResources) {
+        let positions = vec![
+            (Actor::Player, ActorPosition(2, 2)),
+            (Actor::Enemy(0), ActorPosition(3, 2)),
+            (Actor::Enemy(1), ActorPosition(2, 3)),
+            (Actor::Enemy(2), ActorPosition(6, 6)),
+        ];
+        let mut resources = positions
+            .iter()
+            .map(|(actor, _)| {
+                let resource = ActorResource {
+                    health: 5,
+                    max_health: 5,
+                    ..Default::default()
+                };
+                (*actor, resource)
+            })
+            .collect::<HashMap<_, _>>();
+        if let Some(player) = resources.get_mut(&Actor::Player) {
+            player.health = player_health;
+            player.hand = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        }
+        if let Some(enemy) = resources.get_mut(&Actor::Enemy(0)) {
+            enemy.add_status(Status::new(StatusKind::Stun, 1));
+        }
+        let turn_order = positions.iter().map(|(actor, _)| *actor).collect();
+        (
+            positions,
+            ActorResources {
+                resources,
+                turn_order,
+            },
+        )
+    }
+
+    fn context<'a>(
+        positions: &'a [(Actor, ActorPosition)],
+        resources: &'a ActorResources,
+        targets: &'a [(usize, usize)],
+    ) -> FlowContext<'a> {
+        FlowContext {
+            actor: Actor::Player,
+            positions,
+            resources,
+            targets,
+        }
+    }
+
+    fn is_attack(action: &CardAction) -> bool {
+        matches!(
+            action,
+            CardAction::Attack {
+                damage: _,
+                range: _,
+                shape: _,
+                bonus: _,
+            }
+        )
+    }
+
+    #[test]
+    fn repeat_expands_once_per_count() {
+        let (positions, resources) = board(5);
+        let ctx = context(&positions, &resources, &[]);
+        let attack = "      - !Attack\n          damage: 1\n          range: 1\n";
+
+        let mut around = card(&format!(
+            "  - !Repeat\n      times: !EnemiesWithin 1\n      actions:\n{attack}  - !Move 1\n"
+        ));
+        assert_eq!(advance(&mut around, 0, &ctx), NextStep::Perform(0));
+        assert_eq!(around.actions.len(), 3);
+        assert!(around.actions[..2].iter().all(is_attack));
+
+        let mut per_card = card(&format!(
+            "  - !Repeat\n      times: CardsInHand\n      actions:\n{attack}"
+        ));
+        advance(&mut per_card, 0, &ctx);
+        assert_eq!(per_card.actions.len(), 3);
+
+        let mut capped = card(&format!(
+            "  - !Repeat\n      times: !Fixed 100\n      actions:\n{attack}"
+        ));
+        advance(&mut capped, 0, &ctx);
+        assert_eq!(capped.actions.len(), MAX_REPEATS);
+
+        let mut never = card(&format!(
+            "  - !Repeat\n      times: !Fixed 0\n      actions:\n{attack}"
+        ));
+        assert_eq!(advance(&mut never, 0, &ctx), NextStep::Done);
+    }
+
+    #[test]
+    fn if_follows_the_branch_its_condition_picks() {
+        let guard =
+            "  - !If\n      condition: !CasterHealthAtMost 2\n      then:\n        - !Block 1\n";
+        let (positions, resources) = board(5);
+        let mut healthy = card(guard);
+        assert_eq!(
+            advance(&mut healthy, 0, &context(&positions, &resources, &[])),
+            NextStep::Done
+        );
+
+        let (positions, resources) = board(2);
+        let mut hurt = card(guard);
+        assert_eq!(
+            advance(&mut hurt, 0, &context(&positions, &resources, &[])),
+            NextStep::Perform(0)
+        );
+        assert!(matches!(hurt.actions[0], CardAction::Block(1)));
+
+        let finisher = "  - !If\n      condition: !TargetHas Stun\n      then:\n        - !Block 1\n      otherwise:\n        - !Move 1\n";
+        let mut stunned = card(finisher);
+        advance(&mut stunned, 0, &context(&positions, &resources, &[(3, 2)]));
+        assert!(matches!(stunned.actions[0], CardAction::Block(1)));
+        let mut awake = card(finisher);
+        advance(&mut awake, 0, &context(&positions, &resources, &[(2, 3)]));
+        assert!(matches!(awake.actions[0], CardAction::Move(1)));
+    }
+
+    #[test]
+    fn choose_waits_for_an_option() {
+        let (positions, resources) = board(5);
+        let ctx = context(&positions, &resources, &[]);
+        let mut feint = card(
+            "  - !Choose\n    - - !Move 3\n    - - !Attack\n          damage: 2\n          range: 1\n",
+        );
+
+        assert_eq!(advance(&mut feint, 0, &ctx), NextStep::Choose(0));
+        assert_eq!(options(&feint, 0).len(), 2);
+        choose(&mut feint, 0, 1);
+        assert_eq!(advance(&mut feint, 0, &ctx), NextStep::Perform(0));
+        assert!(is_attack(&feint.actions[0]));
+
+        let mut only = card("  - !Choose\n    - - !Move 3\n");
+        assert_eq!(advance(&mut only, 0, &ctx), NextStep::Perform(0));
+        assert!(options(&only, 0).is_empty());
+    }
+
+    #[test]
+    fn bonus_damage_adds_up_the_bonuses_that_hold() {
+        let (positions, resources) = board(5);
+        let bonus = serde_yaml::from_str::<Vec<Bonus>>(
+            "
+- when: !TargetHas Stun
+  damage: 2
+- when: !AtLeast [!EnemiesWithin 1, 2]
+  damage: 1
+- when: !Any [!CasterHealthAtMost 4, !CasterHas Shield]
+  damage: 4
+- when: !All [!TargetHas Stun, !CasterHas Stun]
+  damage: 8
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            bonus_damage(&bonus, &context(&positions, &resources, &[(3, 2)])),
+            3
+        );
+        assert_eq!(
+            bonus_damage(&bonus, &context(&positions, &resources, &[(2, 3)])),
+            1
+        );
+        let (positions, resources) = board(4);
+        assert_eq!(
+            bonus_damage(&bonus, &context(&positions, &resources, &[(2, 3)])),
+            5
+        );
+    }
+}
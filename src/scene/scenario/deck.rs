@@ -0,0 +1,121 @@
+use bevy_turborand::{DelegatedRng, RngComponent};
+
+use super::types::ActorResource;
+use crate::card::Card;
+
+pub const HAND_SIZE: usize = 5;
+pub const MAX_HAND_SIZE: usize = 8;
+
+impl ActorResource {
+    pub fn with_deck(self, deck: Vec<String>, shuffle_seed: u64) -> Self {
+        let mut resource = Self {
+            draw_pile: deck,
+            shuffle_seed,
+            ..self
+        };
+        resource.shuffle_draw_pile();
+        resource
+    }
+
+    pub fn owned_cards(&self) -> impl Iterator<Item = &String> {
+        self.draw_pile
+            .iter()
+            .chain(self.hand.iter())
+            .chain(self.discarded.iter())
+            .chain(self.exhausted.iter())
+    }
+
+    pub fn add_to_deck(&mut self, mut cards: Vec<String>) {
+        self.draw_pile.append(&mut cards);
+        self.shuffle_draw_pile();
+    }
+
+    pub fn upgrade_card(&mut self, base: &str, upgraded: &str) -> bool {
+        let card = self
+            .draw_pile
+            .iter_mut()
+            .chain(self.hand.iter_mut())
+            .chain(self.discarded.iter_mut())
+            .chain(self.exhausted.iter_mut())
+            .find(|id| *id == base);
+        match card {
+            Some(card) => {
+                *card = upgraded.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn draw(&mut self, count: usize) -> usize {
+        let mut drawn = 0;
+        while drawn < count && self.hand.len() < MAX_HAND_SIZE {
+            if self.draw_pile.is_empty() {
+                if self.discarded.is_empty() {
+                    break;
+                }
+                self.draw_pile.append(&mut self.discarded);
+                self.shuffle_draw_pile();
+            }
+            match self.draw_pile.pop() {
+                Some(card) => self.hand.push(card),
+                None => break,
+            }
+            drawn += 1;
+        }
+        drawn
+    }
+
+    pub fn draw_to_hand_size(&mut self) -> usize {
+        self.draw(HAND_SIZE.saturating_sub(self.hand.len()))
+    }
+
+    pub fn play_from_hand(&mut self, card: &Card) -> bool {
+        let index = match self.hand.iter().position(|c| c == &card.id) {
+            Some(index) => index,
+            None => return false,
+        };
+        let id = self.hand.remove(index);
+        if card.exhaust {
+            self.exhausted.push(id);
+        } else {
+            self.discarded.push(id);
+        }
+        true
+    }
+
+    pub fn discard_hand(&mut self) {
+        self.discarded.append(&mut self.hand);
+    }
+
+    pub fn next_shuffle_seed(&mut self) -> u64 {
+        let mut rng = RngComponent::with_seed(self.shuffle_seed);
+        self.shuffle_seed = rng.u64(..);
+        rng.u64(..)
+    }
+
+    fn shuffle_draw_pile(&mut self) {
+        let mut rng = RngComponent::with_seed(self.shuffle_seed);
+        for i in (1..self.draw_pile.len()).rev() {
+            let j = rng.usize(0..=i);
+            self.draw_pile.swap(i, j);
+        }
+        self.shuffle_seed = rng.u64(..);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exhausted_cards_can_be_upgraded() {
+        let mut resource = ActorResource {
+            exhausted: vec!["strike".to_string()],
+            ..Default::default()
+        };
+        assert!(resource.upgrade_card("strike", "strike+"));
+        assert_eq!(resource.exhausted, vec!["strike+".to_string()]);
+        assert!(!resource.upgrade_card("strike", "strike+"));
+    }
+}
@@ -1,11 +1,477 @@
-use crate::card::Cards;
-use bevy::prelude::*;
+use crate::{
+    card::{Card, CardAction, Cards},
+    enemy::{AiProfile, Enemies, EnemyDefinition},
+    run_seed::RunRngs,
+};
+use bevy::{prelude::*, utils::HashMap};
 
-use super::*;
+use super::{
+    card_flow::{self, FlowContext, NextStep},
+    scenario_map::ScenarioMap,
+    scenario_utilities,
+    simulation::{self, TurnStart},
+    *,
+};
 
-use bevy_turborand::GlobalRng;
+use bevy_turborand::{DelegatedRng, RngComponent};
 
-use bevy_turborand::DelegatedRng;
+pub mod personality;
+
+use personality::*;
+
+pub struct BattleView<'a> {
+    pub map: &'a ScenarioMap,
+    pub positions: &'a [(Actor, ActorPosition)],
+    pub resources: &'a ActorResources,
+    pub cards: &'a HashMap<String, Card>,
+    pub enemies: &'a HashMap<String, EnemyDefinition>,
+}
+
+pub trait EnemyPolicy: Send + Sync {
+    fn choose_card(
+        &self,
+        rng: &mut RngComponent,
+        view: &BattleView,
+        actor: &Actor,
+    ) -> Option<String>;
+
+    fn select_targets(
+        &self,
+        rng: &mut RngComponent,
+        view: &BattleView,
+        selection: &TargetSelection,
+    ) -> Vec<(usize, usize)>;
+
+    fn choose_option(
+        &self,
+        rng: &mut RngComponent,
+        view: &BattleView,
+        actor: &Actor,
+        card: &Card,
+        index: usize,
+    ) -> usize;
+}
+
+pub fn policy(profile: AiProfile) -> Box<dyn EnemyPolicy> {
+    match profile {
+        AiProfile::Random => Box::new(RandomPolicy),
+        AiProfile::Planner => Box::new(Planner(Balanced)),
+        AiProfile::Aggressive => Box::new(Planner(Aggressive)),
+        AiProfile::Cautious => Box::new(Planner(Cautious)),
+        AiProfile::Support => Box::new(Planner(Support)),
+        AiProfile::Guard => Box::new(Planner(Guard)),
+    }
+}
+
+/// The profile on an actor's resources is the one source of its behaviour, so the board and the
+/// headless simulation always make the same decisions
+pub fn policy_for(resources: &ActorResources, actor: &Actor) -> Box<dyn EnemyPolicy> {
+    policy(
+        resources
+            .resources
+            .get(actor)
+            .map(|res| res.ai)
+            .unwrap_or_default(),
+    )
+}
+
+pub struct RandomPolicy;
+
+impl EnemyPolicy for RandomPolicy {
+    fn choose_card(
+        &self,
+        rng: &mut RngComponent,
+        view: &BattleView,
+        actor: &Actor,
+    ) -> Option<String> {
+        let playable = simulation::playable_cards(view.resources, actor, view.cards);
+        choose_card(rng, &playable)
+    }
+
+    fn select_targets(
+        &self,
+        rng: &mut RngComponent,
+        _view: &BattleView,
+        selection: &TargetSelection,
+    ) -> Vec<(usize, usize)> {
+        select_target(rng, selection)
+    }
+
+    fn choose_option(
+        &self,
+        rng: &mut RngComponent,
+        _view: &BattleView,
+        _actor: &Actor,
+        card: &Card,
+        index: usize,
+    ) -> usize {
+        choose_option(rng, card, index)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlanWeights {
+    pub damage: f32,
+    pub kill: f32,
+    pub status: f32,
+    pub friendly_fire: f32,
+    pub approach: f32,
+    pub exposure: f32,
+}
+
+impl Default for PlanWeights {
+    fn default() -> Self {
+        Self {
+            damage: 10.,
+            kill: 100.,
+            status: 4.,
+            friendly_fire: 8.,
+            approach: 1.,
+            exposure: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Planner<P: Personality>(pub P);
+
+struct Plan {
+    score: f32,
+    targets: Vec<Vec<(usize, usize)>>,
+    option: Option<usize>,
+}
+
+impl<P: Personality> Planner<P> {
+    fn plan(&self, view: &BattleView, actor: &Actor, card: &Card, action_index: usize) -> Plan {
+        let mut budget = PLAN_BUDGET;
+        self.search(
+            view,
+            actor,
+            card.clone(),
+            action_index,
+            view.positions.to_vec(),
+            view.resources.clone(),
+            &[],
+            &mut budget,
+        )
+    }
+
+    fn search(
+        &self,
+        view: &BattleView,
+        actor: &Actor,
+        mut card: Card,
+        action_index: usize,
+        positions: Vec<(Actor, ActorPosition)>,
+        resources: ActorResources,
+        previous_targets: &[(usize, usize)],
+        budget: &mut usize,
+    ) -> Plan {
+        let ctx = FlowContext {
+            actor: *actor,
+            positions: &positions,
+            resources: &resources,
+            targets: previous_targets,
+        };
+        let action_index = match card_flow::advance(&mut card, action_index, &ctx) {
+            NextStep::Perform(action_index) => action_index,
+            NextStep::Choose(action_index) => {
+                return self.search_options(
+                    view,
+                    actor,
+                    &card,
+                    action_index,
+                    positions,
+                    resources,
+                    budget,
+                )
+            }
+            NextStep::Done => {
+                return Plan {
+                    score: self.score(view, actor, &positions, &resources),
+                    targets: vec![],
+                    option: None,
+                }
+            }
+        };
+        let action = match card.actions.get(action_index) {
+            Some(action) => action,
+            None => {
+                return Plan {
+                    score: self.score(view, actor, &positions, &resources),
+                    targets: vec![],
+                    option: None,
+                }
+            }
+        };
+
+        let targetable = action.target();
+        let valid_targets = scenario_utilities::propose_valid_targets(
+            actor,
+            &targetable,
+            &positions,
+            view.map,
+            &resources,
+        );
+        let mut options = target_options(&valid_targets, action.num_targets());
+        if options.is_empty() {
+            options.push(vec![]);
+        }
+
+        *budget = budget.saturating_sub(options.len());
+        let mut outcomes = options
+            .into_iter()
+            .map(|option| {
+                let mut positions = positions.clone();
+                let mut resources = resources.clone();
+                let mut map = view.map.clone();
+                let _ = simulation::resolve_action(
+                    actor,
+                    action,
+                    &option,
+                    &mut map,
+                    view.enemies,
+                    view.cards,
+                    &mut positions,
+                    &mut resources,
+                );
+                let score = self.score(view, actor, &positions, &resources);
+                (score, option, positions, resources)
+            })
+            .collect::<Vec<_>>();
+        // Follow up the most promising options first - once the budget runs out only the
+        // greedy pick is followed, so a deep card can't blow up into every combination
+        outcomes.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut best: Option<Plan> = None;
+        for (i, (_, option, positions, resources)) in outcomes.into_iter().enumerate() {
+            if i > 0 && *budget == 0 {
+                break;
+            }
+            let mut plan = self.search(
+                view,
+                actor,
+                card.clone(),
+                action_index + 1,
+                positions,
+                resources,
+                &option,
+                budget,
+            );
+            if best.as_ref().map(|b| plan.score > b.score).unwrap_or(true) {
+                plan.targets.insert(0, option);
+                best = Some(plan);
+            }
+        }
+        best.unwrap_or(Plan {
+            score: f32::MIN,
+            targets: vec![],
+            option: None,
+        })
+    }
+
+    fn search_options(
+        &self,
+        view: &BattleView,
+        actor: &Actor,
+        card: &Card,
+        action_index: usize,
+        positions: Vec<(Actor, ActorPosition)>,
+        resources: ActorResources,
+        budget: &mut usize,
+    ) -> Plan {
+        let mut best: Option<Plan> = None;
+        for option in 0..card_flow::options(card, action_index).len() {
+            if option > 0 && *budget == 0 {
+                break;
+            }
+            let mut card = card.clone();
+            card_flow::choose(&mut card, action_index, option);
+            let mut plan = self.search(
+                view,
+                actor,
+                card,
+                action_index,
+                positions.clone(),
+                resources.clone(),
+                &[],
+                budget,
+            );
+            if best.as_ref().map(|b| plan.score > b.score).unwrap_or(true) {
+                plan.option = Some(option);
+                best = Some(plan);
+            }
+        }
+        best.unwrap_or(Plan {
+            score: f32::MIN,
+            targets: vec![],
+            option: None,
+        })
+    }
+
+    fn score(
+        &self,
+        view: &BattleView,
+        actor: &Actor,
+        positions: &[(Actor, ActorPosition)],
+        resources: &ActorResources,
+    ) -> f32 {
+        let weights = self.0.weights();
+        let health = |resources: &ActorResources, a: &Actor| {
+            resources
+                .resources
+                .get(a)
+                .map(|r| r.health as f32)
+                .unwrap_or(0.)
+        };
+        let mut score = 0.;
+
+        let player_before = view.resources.resources.get(&Actor::Player);
+        let player_after = resources.resources.get(&Actor::Player);
+        if let (Some(before), Some(after)) = (player_before, player_after) {
+            score += (before.health.saturating_sub(after.health)) as f32 * weights.damage;
+            if after.health == 0 {
+                score += weights.kill;
+            }
+            score += (after.status_pressure() - before.status_pressure()) * weights.status;
+        }
+
+        for a in view.resources.resources.keys() {
+            if let Actor::Enemy(_) = a {
+                score -= (health(view.resources, a) - health(resources, a)) * weights.friendly_fire;
+                if let (Some(before), Some(after)) =
+                    (view.resources.resources.get(a), resources.resources.get(a))
+                {
+                    score -= (after.status_pressure() - before.status_pressure()) * weights.status;
+                }
+            }
+        }
+
+        if let (Some(me), Some(player)) = (
+            position_of(positions, actor),
+            position_of(positions, &Actor::Player),
+        ) {
+            let distance = distance(me, player);
+            score -= distance as f32 * weights.approach;
+            if distance <= attack_range(view, &Actor::Player) {
+                score -= weights.exposure;
+            }
+        }
+
+        score + self.0.bonus(view, actor, positions, resources)
+    }
+}
+
+const MAX_TARGET_OPTIONS: usize = 64;
+// How many actions a single plan may resolve before it stops branching
+const PLAN_BUDGET: usize = 1024;
+
+fn target_options(valid_targets: &[(usize, usize)], count: usize) -> Vec<Vec<(usize, usize)>> {
+    let total = valid_targets.len();
+    let count = count.min(total);
+    let mut options = vec![];
+    if count == 0 {
+        return options;
+    }
+    let mut indices = (0..count).collect::<Vec<_>>();
+    while options.len() < MAX_TARGET_OPTIONS {
+        options.push(indices.iter().map(|i| valid_targets[*i]).collect());
+        let slot = match (0..count).rev().find(|i| indices[*i] < total - count + i) {
+            Some(slot) => slot,
+            None => break,
+        };
+        let base = indices[slot] + 1;
+        for (offset, index) in indices.iter_mut().skip(slot).enumerate() {
+            *index = base + offset;
+        }
+    }
+    options
+}
+
+pub(crate) fn position_of(
+    positions: &[(Actor, ActorPosition)],
+    actor: &Actor,
+) -> Option<(usize, usize)> {
+    positions
+        .iter()
+        .find_map(|(a, p)| if a == actor { Some(p.into()) } else { None })
+}
+
+pub(crate) fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1))
+}
+
+pub(crate) fn attack_range(view: &BattleView, actor: &Actor) -> usize {
+    view.resources
+        .resources
+        .get(actor)
+        .map(|res| {
+            res.owned_cards()
+                .filter_map(|id| view.cards.get(id))
+                .flat_map(|card| card.actions.iter())
+                .flat_map(|action| action.flattened())
+                .filter_map(|action| match action {
+                    CardAction::Attack {
+                        damage: _,
+                        range,
+                        shape: _,
+                        bonus: _,
+                    } => Some(*range),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0)
+}
+
+impl<P: Personality> EnemyPolicy for Planner<P> {
+    fn choose_card(
+        &self,
+        _rng: &mut RngComponent,
+        view: &BattleView,
+        actor: &Actor,
+    ) -> Option<String> {
+        let playable = simulation::playable_cards(view.resources, actor, view.cards);
+        let mut best: Option<(f32, &String)> = None;
+        for id in playable.iter() {
+            if let Some(card) = view.cards.get(id) {
+                let plan = self.plan(view, actor, card, 0);
+                if best.map(|(score, _)| plan.score > score).unwrap_or(true) {
+                    best = Some((plan.score, id));
+                }
+            }
+        }
+        best.map(|(_, id)| id.clone())
+    }
+
+    fn select_targets(
+        &self,
+        _rng: &mut RngComponent,
+        view: &BattleView,
+        selection: &TargetSelection,
+    ) -> Vec<(usize, usize)> {
+        self.plan(view, &selection.actor, &selection.card, selection.action_id)
+            .targets
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|t| selection.valid_targets.contains(t))
+            .collect()
+    }
+
+    fn choose_option(
+        &self,
+        _rng: &mut RngComponent,
+        view: &BattleView,
+        actor: &Actor,
+        card: &Card,
+        index: usize,
+    ) -> usize {
+        self.plan(view, actor, card, index).option.unwrap_or(0)
+    }
+}
 
 pub fn select_target<T: DelegatedRng>(
     rng: &mut T,
@@ -29,39 +495,67 @@ pub fn select_target<T: DelegatedRng>(
         .collect()
 }
 
+pub fn choose_option<T: DelegatedRng>(rng: &mut T, card: &Card, index: usize) -> usize {
+    let options = card_flow::options(card, index).len();
+    if options == 0 {
+        return 0;
+    }
+    rng.usize(0..options)
+}
+
+pub fn choose_card<T: DelegatedRng>(rng: &mut T, hand: &[String]) -> Option<String> {
+    if hand.is_empty() {
+        return None;
+    }
+    hand.get(rng.usize(0..hand.len())).cloned()
+}
+
 pub(crate) fn choose_enemy_card(
     mut commands: Commands,
     mut events: EventWriter<CardPlayedEvent>,
-    mut global_rng: ResMut<GlobalRng>,
+    mut rngs: ResMut<RunRngs>,
     current_turn_process: Option<Res<CurrentTurnProcess>>,
     resources: Option<ResMut<ActorResources>>,
-    _cards: Res<Cards>,
+    map: Option<Res<ScenarioMap>>,
+    position_query: Query<(&Actor, &ActorPosition)>,
+    cards: Res<Cards>,
+    enemies: Res<Enemies>,
 ) {
     info!("Choosing enemy card...");
-    if let (Some(process), Some(mut resources)) = (current_turn_process, resources) {
+    if let (Some(process), Some(mut resources), Some(map)) = (current_turn_process, resources, map)
+    {
         info!("Process can continue");
         match *process {
-            CurrentTurnProcess::Thinking(actor) => {
-                if let Some(res) = resources.resources.get_mut(&actor) {
-                    if res.stun_duration > 0 {
-                        res.stun_duration = res.stun_duration.checked_sub(1).unwrap_or_default();
-                        info!("Stunned");
+            CurrentTurnProcess::Thinking(actor) | CurrentTurnProcess::CardResolved(actor) => {
+                if let CurrentTurnProcess::Thinking(_) = *process {
+                    if simulation::start_turn(&mut resources, &actor) != TurnStart::Ready {
+                        info!("Turn skipped");
                         commands.insert_resource(CurrentTurnProcess::Done(actor));
                         return;
                     }
-                    let hand = &res.hand;
-                    let range = 0..hand.len();
-                    let selected = global_rng.usize(range);
-                    if let Some(selected) = hand.get(selected) {
-                        info!("Playing a card {:?}", selected);
-                        events.send(CardPlayedEvent {
-                            actor,
-                            card: selected.clone(),
-                        });
-                        return;
-                    }
                 }
-                info!("Couldn't play anything, skipping turn");
+                let positions = position_query
+                    .iter()
+                    .map(|(a, p)| (*a, *p))
+                    .collect::<Vec<_>>();
+                let view = BattleView {
+                    map: &map,
+                    positions: &positions,
+                    resources: &resources,
+                    cards: &cards.cards,
+                    enemies: &enemies.enemies,
+                };
+                if let Some(selected) =
+                    policy_for(&resources, &actor).choose_card(&mut rngs.enemy_ai, &view, &actor)
+                {
+                    info!("Playing a card {:?}", selected);
+                    events.send(CardPlayedEvent {
+                        actor,
+                        card: selected,
+                    });
+                    return;
+                }
+                info!("Nothing left to play, ending turn");
                 commands.insert_resource(CurrentTurnProcess::Done(actor));
             }
             _ => {
@@ -70,3 +564,246 @@ pub(crate) fn choose_enemy_card(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARDS: [&str; 4] = [
+        "
+id: jab
+name: Jab
+actions:
+  - !Attack
+    damage: 1
+    range: 1
+tier: 0
+",
+        "
+id: strike
+name: Strike
+actions:
+  - !Move 2
+  - !Attack
+    damage: 2
+    range: 1
+tier: 0
+",
+        "
+id: shot
+name: Shot
+actions:
+  - !Move 2
+  - !Attack
+    damage: 1
+    range: 2
+tier: 0
+",
+        "
+id: lance
+name: Lance
+actions:
+  - !Attack
+    damage: 2
+    range: 1
+    shape: !Line
+      length: 3
+tier: 0
+",
+    ];
+
+    const ROOM: &str = "
+#######
+#.....#
+#..E..#
+#..S..#
+#.....#
+#######
+";
+
+    const FIELD: &str = "
+##############
+#S...........#
+#............#
+#............#
+#...E........#
+#............#
+#.....1.....E#
+##############
+";
+
+    const FIRING_LINE: &str = "
+#######
+#....1#
+#EEES.#
+#.....#
+#######
+";
+
+    struct Board {
+        map: ScenarioMap,
+        positions: Vec<(Actor, ActorPosition)>,
+        resources: ActorResources,
+        cards: HashMap<String, Card>,
+        enemies: HashMap<String, EnemyDefinition>,
+    }
+
+    impl Board {
+        fn new(grid: &str, enemies: usize, hand: &[&str]) -> Self {
+            let actor = |hand: &[&str]| ActorResource {
+                health: 3,
+                max_health: 3,
+                hand: hand.iter().map(|id| id.to_string()).collect(),
+                ..Default::default()
+            };
+            let mut resources = (0..enemies)
+                .map(|i| (Actor::Enemy(i), actor(hand)))
+                .collect::<HashMap<_, _>>();
+            let turn_order = simulation::turn_order(&resources);
+            resources.insert(
+                Actor::Player,
+                ActorResource {
+                    health: 10,
+                    max_health: 10,
+                    ..actor(&["jab"])
+                },
+            );
+            let map = ScenarioMap::from_grid(grid);
+            Self {
+                positions: simulation::start_positions(&map),
+                map,
+                resources: ActorResources {
+                    resources,
+                    turn_order,
+                },
+                cards: CARDS
+                    .iter()
+                    .map(|yaml| serde_yaml::from_str::<Card>(yaml).unwrap())
+                    .map(|card| (card.id.clone(), card))
+                    .collect(),
+                enemies: HashMap::default(),
+            }
+        }
+
+        fn view(&self) -> BattleView {
+            BattleView {
+                map: &self.map,
+                positions: &self.positions,
+                resources: &self.resources,
+                cards: &self.cards,
+                enemies: &self.enemies,
+            }
+        }
+
+        fn resource(&mut self, actor: Actor) -> &mut ActorResource {
+            self.resources.resources.get_mut(&actor).unwrap()
+        }
+
+        fn targets(&self, profile: AiProfile, card: &str) -> Vec<(usize, usize)> {
+            let selection = scenario_utilities::target_selection(
+                &Actor::Enemy(0),
+                &self.cards[card],
+                0,
+                &self.positions,
+                &self.map,
+                &self.resources,
+            )
+            .unwrap();
+            policy(profile).select_targets(
+                &mut RngComponent::with_seed(0),
+                &self.view(),
+                &selection,
+            )
+        }
+    }
+
+    #[test]
+    fn the_actor_profile_swaps_the_policy() {
+        let mut board = Board::new(ROOM, 1, &["jab", "strike", "shot"]);
+        let mut choices = |ai: AiProfile| {
+            board.resource(Actor::Enemy(0)).ai = ai;
+            (0..20)
+                .filter_map(|seed| {
+                    policy_for(&board.resources, &Actor::Enemy(0)).choose_card(
+                        &mut RngComponent::with_seed(seed),
+                        &board.view(),
+                        &Actor::Enemy(0),
+                    )
+                })
+                .collect::<std::collections::HashSet<_>>()
+        };
+
+        assert_eq!(choices(AiProfile::Planner).len(), 1);
+        assert!(choices(AiProfile::Random).len() > 1);
+    }
+
+    #[test]
+    fn planner_takes_a_lethal_shot() {
+        let mut board = Board::new(ROOM, 1, &["jab"]);
+        board.resource(Actor::Player).health = 1;
+
+        assert_eq!(board.targets(AiProfile::Planner, "jab"), vec![(3, 3)]);
+    }
+
+    #[test]
+    fn planning_stays_within_budget() {
+        let board = Board::new(ROOM, 1, &["shot"]);
+        let mut budget = 1;
+
+        let plan = Planner(Balanced).search(
+            &board.view(),
+            &Actor::Enemy(0),
+            board.cards["shot"].clone(),
+            0,
+            board.positions.clone(),
+            board.resources.clone(),
+            &[],
+            &mut budget,
+        );
+
+        assert_eq!(budget, 0);
+        assert_eq!(plan.targets.len(), 2);
+    }
+
+    #[test]
+    fn each_profile_moves_for_its_own_reasons() {
+        let board = Board::new(FIELD, 2, &["shot"]);
+        let player = position_of(&board.positions, &Actor::Player).unwrap();
+        let ally = position_of(&board.positions, &Actor::Enemy(1)).unwrap();
+        let post = (6, 6);
+        let planner = board.targets(AiProfile::Planner, "shot")[0];
+
+        assert_eq!(distance(planner, player), 1);
+        assert_eq!(
+            distance(board.targets(AiProfile::Aggressive, "shot")[0], player),
+            1
+        );
+        let support = board.targets(AiProfile::Support, "shot")[0];
+        assert!(distance(support, ally) < distance(planner, ally));
+        let guard = board.targets(AiProfile::Guard, "shot")[0];
+        assert!(distance(guard, post) < distance(planner, post));
+    }
+
+    #[test]
+    fn cautious_keeps_its_distance() {
+        let board = Board::new(FIELD, 2, &["shot"]);
+        let view = board.view();
+        let cautious = board.targets(AiProfile::Cautious, "shot")[0];
+        let player = position_of(&board.positions, &Actor::Player).unwrap();
+
+        assert_eq!(
+            distance(cautious, player),
+            attack_range(&view, &Actor::Enemy(0))
+        );
+        assert!(distance(cautious, player) > attack_range(&view, &Actor::Player));
+    }
+
+    #[test]
+    fn aggressive_accepts_friendly_fire() {
+        let board = Board::new(FIRING_LINE, 3, &["lance"]);
+
+        // The only line that reaches the player runs through both allies
+        assert_eq!(board.targets(AiProfile::Aggressive, "lance"), vec![(2, 2)]);
+        assert_ne!(board.targets(AiProfile::Planner, "lance"), vec![(2, 2)]);
+    }
+}
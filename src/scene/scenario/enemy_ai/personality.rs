@@ -0,0 +1,147 @@
+use super::{attack_range, distance, position_of, BattleView, PlanWeights};
+use crate::scene::scenario::{scenario_map::TileTag, Actor, ActorPosition, ActorResources};
+
+pub trait Personality: Send + Sync {
+    fn weights(&self) -> PlanWeights;
+
+    fn bonus(
+        &self,
+        _view: &BattleView,
+        _actor: &Actor,
+        _positions: &[(Actor, ActorPosition)],
+        _resources: &ActorResources,
+    ) -> f32 {
+        0.
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Balanced;
+
+impl Personality for Balanced {
+    fn weights(&self) -> PlanWeights {
+        PlanWeights::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aggressive;
+
+impl Personality for Aggressive {
+    fn weights(&self) -> PlanWeights {
+        PlanWeights {
+            damage: 15.,
+            friendly_fire: 4.,
+            approach: 3.,
+            exposure: 0.,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cautious;
+
+impl Personality for Cautious {
+    fn weights(&self) -> PlanWeights {
+        PlanWeights {
+            approach: 0.,
+            exposure: 30.,
+            ..Default::default()
+        }
+    }
+
+    // Hangs back at the edge of its own reach - closing in is as bad as drifting out of range
+    fn bonus(
+        &self,
+        view: &BattleView,
+        actor: &Actor,
+        positions: &[(Actor, ActorPosition)],
+        _resources: &ActorResources,
+    ) -> f32 {
+        let range = attack_range(view, actor).max(1);
+        match (
+            position_of(positions, actor),
+            position_of(positions, &Actor::Player),
+        ) {
+            (Some(me), Some(player)) => -2. * distance(me, player).abs_diff(range) as f32,
+            _ => 0.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Support;
+
+impl Personality for Support {
+    fn weights(&self) -> PlanWeights {
+        PlanWeights {
+            damage: 3.,
+            status: 15.,
+            approach: 0.5,
+            exposure: 5.,
+            ..Default::default()
+        }
+    }
+
+    fn bonus(
+        &self,
+        _view: &BattleView,
+        actor: &Actor,
+        positions: &[(Actor, ActorPosition)],
+        _resources: &ActorResources,
+    ) -> f32 {
+        let me = match position_of(positions, actor) {
+            Some(me) => me,
+            None => return 0.,
+        };
+        positions
+            .iter()
+            .filter(|(a, _)| a != actor && a != &Actor::Player)
+            .map(|(_, p)| distance(me, p.into()))
+            .min()
+            .map(|d| -(d as f32))
+            .unwrap_or(0.)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Guard;
+
+impl Guard {
+    fn post(view: &BattleView, actor: &Actor) -> Option<(usize, usize)> {
+        let spawn = view.map.tiles.iter().find_map(|tile| match tile.tag {
+            TileTag::Enemy(a) if &a == actor => Some(tile.pos),
+            _ => None,
+        })?;
+        view.map
+            .tiles
+            .iter()
+            .filter(|tile| matches!(tile.tag, TileTag::Target(_)))
+            .map(|tile| tile.pos)
+            .min_by_key(|pos| distance(spawn, *pos))
+    }
+}
+
+impl Personality for Guard {
+    fn weights(&self) -> PlanWeights {
+        PlanWeights {
+            approach: 0.,
+            exposure: 0.,
+            ..Default::default()
+        }
+    }
+
+    fn bonus(
+        &self,
+        view: &BattleView,
+        actor: &Actor,
+        positions: &[(Actor, ActorPosition)],
+        _resources: &ActorResources,
+    ) -> f32 {
+        match (Self::post(view, actor), position_of(positions, actor)) {
+            (Some(post), Some(me)) => -2. * distance(me, post) as f32,
+            _ => 0.,
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::utils::{HashMap, HashSet};
+
+use super::scenario_map::ScenarioMap;
+
+/// Movement costs from `origin`, flooded out to `max_distance`. It's built per query, but the
+/// flood stops at the movement range so the cost follows the range rather than the board size.
+#[derive(Debug, Clone)]
+pub struct DistanceField {
+    pub origin: (usize, usize),
+    distances: HashMap<(usize, usize), usize>,
+    came_from: HashMap<(usize, usize), (usize, usize)>,
+}
+
+impl DistanceField {
+    pub fn new(
+        map: &ScenarioMap,
+        origin: (usize, usize),
+        occupied: &HashSet<(usize, usize)>,
+        max_distance: usize,
+    ) -> Self {
+        let mut distances = HashMap::new();
+        let mut came_from = HashMap::new();
+        let mut frontier = BinaryHeap::new();
+
+        distances.insert(origin, 0);
+        frontier.push(Reverse((0, origin)));
+
+        while let Some(Reverse((distance, pos))) = frontier.pop() {
+            if distances.get(&pos).map(|d| *d < distance).unwrap_or(false) {
+                continue;
+            }
+            for next in map.neighbours(pos) {
+                if occupied.contains(&next) {
+                    continue;
+                }
+                let cost = match map.move_cost(next) {
+                    Some(cost) => cost,
+                    None => continue,
+                };
+                let next_distance = distance + cost;
+                if next_distance > max_distance {
+                    continue;
+                }
+                if distances
+                    .get(&next)
+                    .map(|d| next_distance < *d)
+                    .unwrap_or(true)
+                {
+                    distances.insert(next, next_distance);
+                    came_from.insert(next, pos);
+                    frontier.push(Reverse((next_distance, next)));
+                }
+            }
+        }
+
+        Self {
+            origin,
+            distances,
+            came_from,
+        }
+    }
+
+    pub fn distance(&self, pos: (usize, usize)) -> Option<usize> {
+        self.distances.get(&pos).copied()
+    }
+
+    pub fn reachable(&self) -> Vec<(usize, usize)> {
+        let mut reachable = self.distances.keys().copied().collect::<Vec<_>>();
+        reachable.sort();
+        reachable
+    }
+
+    pub fn path_to(&self, target: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        if !self.distances.contains_key(&target) {
+            return None;
+        }
+        let mut path = vec![];
+        let mut current = target;
+        while current != self.origin {
+            path.push(current);
+            current = *self.came_from.get(&current)?;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+pub fn find_path(
+    map: &ScenarioMap,
+    from: (usize, usize),
+    to: (usize, usize),
+    occupied: &HashSet<(usize, usize)>,
+) -> Option<Vec<(usize, usize)>> {
+    let heuristic = |pos: (usize, usize)| pos.0.abs_diff(to.0).max(pos.1.abs_diff(to.1));
+    let mut costs = HashMap::new();
+    let mut came_from = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+
+    costs.insert(from, 0);
+    frontier.push(Reverse((heuristic(from), from)));
+
+    while let Some(Reverse((_, pos))) = frontier.pop() {
+        if pos == to {
+            let mut path = vec![];
+            let mut current = to;
+            while current != from {
+                path.push(current);
+                current = *came_from.get(&current)?;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let cost = costs.get(&pos).copied().unwrap_or(0);
+        for next in map.neighbours(pos) {
+            if next != to && occupied.contains(&next) {
+                continue;
+            }
+            let step = match map.move_cost(next) {
+                Some(step) => step,
+                None => continue,
+            };
+            let next_cost = cost + step;
+            if costs.get(&next).map(|c| next_cost < *c).unwrap_or(true) {
+                costs.insert(next, next_cost);
+                came_from.insert(next, pos);
+                frontier.push(Reverse((next_cost + heuristic(next), next)));
+            }
+        }
+    }
+    None
+}
+
+pub fn movement_path(
+    map: &ScenarioMap,
+    from: (usize, usize),
+    to: (usize, usize),
+    occupied: &HashSet<(usize, usize)>,
+) -> Option<Vec<(usize, usize)>> {
+    let mut path = find_path(map, from, to, occupied)?;
+    let last = path.last().copied().unwrap_or(from);
+    let previous = path.iter().rev().nth(1).copied().unwrap_or(from);
+    let slippery = map.rules(last).map(|rules| rules.slippery).unwrap_or(false);
+    if slippery && last != previous {
+        let slide = (
+            (last.0 as i64 + (last.0 as i64 - previous.0 as i64)) as usize,
+            (last.1 as i64 + (last.1 as i64 - previous.1 as i64)) as usize,
+        );
+        if map.move_cost(slide).is_some() && !occupied.contains(&slide) {
+            path.push(slide);
+        }
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLED: &str = "
+#######
+#S.#..#
+#..#..#
+#.....#
+#######
+";
+
+    #[test]
+    fn distance_field_goes_around_walls() {
+        let map = ScenarioMap::from_grid(WALLED);
+        let field = DistanceField::new(&map, (1, 1), &HashSet::new(), 10);
+
+        assert_eq!(field.distance((1, 1)), Some(0));
+        assert_eq!(field.distance((3, 3)), Some(2));
+        assert_eq!(field.distance((4, 1)), Some(4));
+        assert_eq!(field.distance((3, 1)), None);
+        assert_eq!(field.path_to((4, 1)).map(|path| path.len()), Some(4));
+    }
+
+    #[test]
+    fn distance_field_stops_at_its_range() {
+        let map = ScenarioMap::from_grid(WALLED);
+        let field = DistanceField::new(&map, (1, 1), &HashSet::new(), 2);
+
+        assert_eq!(field.distance((3, 3)), Some(2));
+        assert_eq!(field.distance((4, 1)), None);
+        assert_eq!(field.path_to((4, 1)), None);
+    }
+
+    #[test]
+    fn a_star_finds_the_shortest_walkable_path() {
+        let map = ScenarioMap::from_grid(WALLED);
+        let path = find_path(&map, (1, 1), (4, 1), &HashSet::new()).unwrap();
+
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.last(), Some(&(4, 1)));
+        let mut previous = (1, 1);
+        for step in path {
+            assert_eq!(map.move_cost(step), Some(1), "{step:?} isn't walkable");
+            assert!(step.0.abs_diff(previous.0) <= 1 && step.1.abs_diff(previous.1) <= 1);
+            previous = step;
+        }
+    }
+
+    #[test]
+    fn no_route_finds_no_path() {
+        let map = ScenarioMap::from_grid("#####\n#S#.#\n#####");
+        assert_eq!(find_path(&map, (1, 1), (3, 1), &HashSet::new()), None);
+
+        let corridor = ScenarioMap::from_grid("######\n#S..1#\n######");
+        let occupied = [(2, 1)].into_iter().collect::<HashSet<_>>();
+        assert_eq!(find_path(&corridor, (1, 1), (4, 1), &occupied), None);
+        assert!(find_path(&corridor, (1, 1), (2, 1), &occupied).is_some());
+    }
+
+    #[test]
+    fn difficult_terrain_costs_two() {
+        let map = ScenarioMap::from_grid("######\n#S,.1#\n######");
+        let field = DistanceField::new(&map, (1, 1), &HashSet::new(), 3);
+
+        assert_eq!(field.distance((2, 1)), Some(2));
+        assert_eq!(field.distance((3, 1)), Some(3));
+        assert_eq!(field.distance((4, 1)), None);
+    }
+
+    #[test]
+    fn ice_slides_one_tile_further() {
+        let map = ScenarioMap::from_grid("#######\n#S*..1#\n#######");
+        let none = HashSet::new();
+        assert_eq!(
+            movement_path(&map, (1, 1), (2, 1), &none),
+            Some(vec![(2, 1), (3, 1)])
+        );
+
+        let occupied = [(3, 1)].into_iter().collect::<HashSet<_>>();
+        assert_eq!(
+            movement_path(&map, (1, 1), (2, 1), &occupied),
+            Some(vec![(2, 1)])
+        );
+
+        let walled = ScenarioMap::from_grid("#####\n#S*#1\n#####");
+        assert_eq!(
+            movement_path(&walled, (1, 1), (2, 1), &none),
+            Some(vec![(2, 1)])
+        );
+    }
+}
+
+pub fn forced_path(
+    map: &ScenarioMap,
+    from: (usize, usize),
+    direction: (i64, i64),
+    distance: usize,
+    occupied: &HashSet<(usize, usize)>,
+) -> Vec<(usize, usize)> {
+    let mut path = vec![];
+    let mut current = from;
+    if direction == (0, 0) {
+        return path;
+    }
+    for _ in 0..distance {
+        let next = (
+            current.0 as i64 + direction.0,
+            current.1 as i64 + direction.1,
+        );
+        if next.0 < 0 || next.1 < 0 {
+            break;
+        }
+        let next = (next.0 as usize, next.1 as usize);
+        if map.move_cost(next).is_none() || occupied.contains(&next) {
+            break;
+        }
+        path.push(next);
+        current = next;
+    }
+    path
+}
+
+pub fn direction(from: (usize, usize), to: (usize, usize)) -> (i64, i64) {
+    (
+        (to.0 as i64 - from.0 as i64).signum(),
+        (to.1 as i64 - from.1 as i64).signum(),
+    )
+}
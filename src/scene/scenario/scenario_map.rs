@@ -1,36 +1,108 @@
+pub mod generators;
+
 use super::{Actor, ActorResource};
 use bevy::prelude::*;
 
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
 
-use crate::story::Scenario;
+use crate::{map::MapDefinition, story::Scenario};
 
 use bevy_turborand::DelegatedRng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Resource)]
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
 pub struct ScenarioMap {
     pub width: usize,
     pub height: usize,
     pub tiles: Vec<Tile>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Tile {
     pub pos: (usize, usize),
     pub tile_type: TileType,
+    #[serde(skip)]
     pub entity: Option<Entity>,
     pub tag: TileTag,
+    #[serde(default)]
+    pub damage: usize,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum TileType {
     Empty,
     Floor,
     Obstacle,
     Wall,
+    Difficult,
+    Hazard,
+    Spring,
+    Ice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerrainRules {
+    pub move_cost: Option<usize>,
+    pub end_of_turn: Option<TerrainEffect>,
+    pub slippery: bool,
+    pub health: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainEffect {
+    Damage(usize),
+    Heal(usize),
+}
+
+impl TileType {
+    pub fn rules(&self) -> TerrainRules {
+        let walkable = TerrainRules {
+            move_cost: Some(1),
+            end_of_turn: None,
+            slippery: false,
+            health: None,
+        };
+        match self {
+            TileType::Empty => TerrainRules {
+                move_cost: None,
+                ..walkable
+            },
+            TileType::Obstacle => TerrainRules {
+                move_cost: None,
+                health: Some(2),
+                ..walkable
+            },
+            TileType::Wall => TerrainRules {
+                move_cost: None,
+                health: Some(4),
+                ..walkable
+            },
+            TileType::Floor => walkable,
+            TileType::Difficult => TerrainRules {
+                move_cost: Some(2),
+                ..walkable
+            },
+            TileType::Hazard => TerrainRules {
+                end_of_turn: Some(TerrainEffect::Damage(1)),
+                ..walkable
+            },
+            TileType::Spring => TerrainRules {
+                end_of_turn: Some(TerrainEffect::Heal(1)),
+                ..walkable
+            },
+            TileType::Ice => TerrainRules {
+                slippery: true,
+                ..walkable
+            },
+        }
+    }
+
+    pub fn is_walkable(&self) -> bool {
+        self.rules().move_cost.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TileTag {
     None,
     Start,
@@ -55,9 +127,25 @@ impl ScenarioMap {
         rng: &mut T,
         scenario: &Scenario,
         resources: &HashMap<Actor, ActorResource>,
+        maps: &HashMap<String, MapDefinition>,
     ) -> ScenarioMap {
-        let width = rng.usize(10..=20);
-        let height = rng.usize(10..=20);
+        let style = &scenario.map;
+        if let Some(name) = &style.authored {
+            match maps.get(name) {
+                Some(definition) => {
+                    match Self::from_definition(rng, definition, scenario, resources) {
+                        Ok(map) => return map,
+                        Err(e) => bevy::log::error!("Couldn't use map {name} - {e}"),
+                    }
+                }
+                None => bevy::log::warn!("Map {name} doesn't exist - generating one instead"),
+            }
+        }
+
+        let width = rng.usize(style.min_size..=style.max_size);
+        let height = rng.usize(style.min_size..=style.max_size);
+
+        let layout = style.layout.generate(rng, width, height);
 
         let width_tiles = 0usize..width;
 
@@ -65,10 +153,12 @@ impl ScenarioMap {
             .into_iter()
             .flat_map(|w| {
                 let height_tiles = 0usize..height;
+                let layout = &layout;
                 height_tiles
                     .into_iter()
-                    .map(|h| Tile {
+                    .map(move |h| Tile {
                         pos: (w, h),
+                        tile_type: layout.get(w * height + h).copied().unwrap_or_default(),
                         ..Default::default()
                     })
                     .collect::<Vec<_>>()
@@ -86,43 +176,272 @@ impl ScenarioMap {
             );
         }
 
-        for (actor, _resource) in resources {
+        let mut enemies = resources
+            .keys()
+            .filter(|actor| **actor != Actor::Player)
+            .copied()
+            .collect::<Vec<_>>();
+        enemies.sort();
+
+        for actor in enemies {
             random_place_tile(
                 rng,
                 &mut tiles,
                 Some(TileType::Floor),
-                Some(TileTag::Enemy(*actor)),
+                Some(TileTag::Enemy(actor)),
             );
         }
 
-        for tile in tiles.iter_mut() {
-            if tile.tile_type != TileType::Empty || tile.tag != TileTag::None {
-                continue;
+        let mut map = ScenarioMap {
+            width,
+            height,
+            tiles,
+        };
+        map.connect_targets();
+        map
+    }
+
+    pub fn from_definition<T: DelegatedRng>(
+        rng: &mut T,
+        definition: &MapDefinition,
+        scenario: &Scenario,
+        resources: &HashMap<Actor, ActorResource>,
+    ) -> Result<ScenarioMap, String> {
+        let grid = definition.parse_grid()?;
+        let mut tiles = grid.tiles;
+
+        for (i, _) in scenario.goals.iter().enumerate() {
+            if !tiles.iter().any(|tile| tile.tag == TileTag::Target(i)) {
+                bevy::log::warn!("Map {} has no tile for goal {}", definition.id, i + 1);
+                random_place_tile(
+                    rng,
+                    &mut tiles,
+                    Some(TileType::Floor),
+                    Some(TileTag::Target(i)),
+                );
             }
-            let probability = rng.f32_normalized();
-            tile.tile_type = match probability {
-                p if p < 0.7 => TileType::Floor,
-                p if p < 0.8 => TileType::Obstacle,
-                p if p < 0.9 => TileType::Wall,
-                _ => TileType::Empty,
-            };
         }
 
-        ScenarioMap {
-            width,
-            height,
+        let mut enemies = resources
+            .keys()
+            .filter(|actor| **actor != Actor::Player)
+            .copied()
+            .collect::<Vec<_>>();
+        enemies.sort();
+
+        let mut spawns = grid.enemy_spawns.into_iter();
+        for actor in enemies {
+            match spawns
+                .next()
+                .and_then(|pos| tiles.iter_mut().find(|tile| tile.pos == pos))
+            {
+                Some(tile) => tile.tag = TileTag::Enemy(actor),
+                None => random_place_tile(
+                    rng,
+                    &mut tiles,
+                    Some(TileType::Floor),
+                    Some(TileTag::Enemy(actor)),
+                ),
+            }
+        }
+
+        let mut map = ScenarioMap {
+            width: grid.width,
+            height: grid.height,
             tiles,
+        };
+        if !map.is_connected() {
+            bevy::log::warn!(
+                "Map {} has goals that can't be reached - carving a path to them",
+                definition.id
+            );
+            map.connect_targets();
+        }
+        Ok(map)
+    }
+
+    pub fn tile(&self, pos: (usize, usize)) -> Option<&Tile> {
+        if pos.0 >= self.width || pos.1 >= self.height {
+            return None;
+        }
+        self.tiles
+            .get(pos.0 * self.height + pos.1)
+            .filter(|tile| tile.pos == pos)
+            .or_else(|| self.tiles.iter().find(|tile| tile.pos == pos))
+    }
+
+    fn tile_mut(&mut self, pos: (usize, usize)) -> Option<&mut Tile> {
+        self.tiles.iter_mut().find(|tile| tile.pos == pos)
+    }
+
+    pub fn move_cost(&self, pos: (usize, usize)) -> Option<usize> {
+        self.tile(pos)?.tile_type.rules().move_cost
+    }
+
+    pub fn rules(&self, pos: (usize, usize)) -> Option<TerrainRules> {
+        self.tile(pos).map(|tile| tile.tile_type.rules())
+    }
+
+    pub fn is_destructible(&self, pos: (usize, usize)) -> bool {
+        self.rules(pos)
+            .map(|rules| rules.health.is_some())
+            .unwrap_or(false)
+    }
+
+    pub fn damage_tile(&mut self, pos: (usize, usize), damage: usize) -> bool {
+        if let Some(tile) = self.tile_mut(pos) {
+            if let Some(health) = tile.tile_type.rules().health {
+                tile.damage += damage;
+                if tile.damage >= health {
+                    tile.tile_type = TileType::Floor;
+                    tile.damage = 0;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn start(&self) -> Option<(usize, usize)> {
+        self.tiles
+            .iter()
+            .find(|tile| tile.tag == TileTag::Start)
+            .map(|tile| tile.pos)
+    }
+
+    pub fn targets(&self) -> Vec<(usize, (usize, usize))> {
+        let mut targets = self
+            .tiles
+            .iter()
+            .filter_map(|tile| match tile.tag {
+                TileTag::Target(i) => Some((i, tile.pos)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets
+    }
+
+    pub fn neighbours(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (-1i32..=1)
+            .flat_map(|x| (-1i32..=1).map(move |y| (x, y)))
+            .filter(|offset| *offset != (0, 0))
+            .filter_map(move |(x, y)| {
+                let x = pos.0 as i32 + x;
+                let y = pos.1 as i32 + y;
+                if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+                    None
+                } else {
+                    Some((x as usize, y as usize))
+                }
+            })
+    }
+
+    pub fn reachable_from(&self, start: (usize, usize)) -> HashSet<(usize, usize)> {
+        let mut reached = HashSet::new();
+        let mut to_check = vec![start];
+        reached.insert(start);
+        while let Some(pos) = to_check.pop() {
+            for next in self.neighbours(pos) {
+                let walkable = self
+                    .tile(next)
+                    .map(|tile| tile.tile_type.is_walkable())
+                    .unwrap_or(false);
+                if walkable && reached.insert(next) {
+                    to_check.push(next);
+                }
+            }
+        }
+        reached
+    }
+
+    pub fn is_connected(&self) -> bool {
+        match self.start() {
+            Some(start) => {
+                let reachable = self.reachable_from(start);
+                self.targets()
+                    .iter()
+                    .all(|(_, pos)| reachable.contains(pos))
+            }
+            None => false,
+        }
+    }
+
+    pub fn unreachable_targets(&self) -> Vec<usize> {
+        let reachable = self
+            .start()
+            .map(|start| self.reachable_from(start))
+            .unwrap_or_default();
+        self.targets()
+            .into_iter()
+            .filter(|(_, pos)| !reachable.contains(pos))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn connect_targets(&mut self) {
+        let start = match self.start() {
+            Some(start) => start,
+            None => return,
+        };
+        for (_, target) in self.targets() {
+            let reachable = self.reachable_from(start);
+            if reachable.contains(&target) {
+                continue;
+            }
+            let closest = reachable
+                .iter()
+                .copied()
+                .min_by_key(|pos| (distance(*pos, target), *pos))
+                .unwrap_or(start);
+            self.carve_path(closest, target);
+        }
+    }
+
+    fn carve_path(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let step = |from: usize, to: usize| match from.cmp(&to) {
+            std::cmp::Ordering::Less => from + 1,
+            std::cmp::Ordering::Equal => from,
+            std::cmp::Ordering::Greater => from - 1,
+        };
+        let mut pos = from;
+        while pos != to {
+            pos = (step(pos.0, to.0), step(pos.1, to.1));
+            if let Some(tile) = self.tile_mut(pos) {
+                tile.tile_type = TileType::Floor;
+            }
         }
     }
 }
 
+fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1))
+}
+
 pub(crate) fn random_place_tile<T: DelegatedRng>(
     rng: &mut T,
-    tiles: &mut Vec<Tile>,
+    tiles: &mut [Tile],
     tile_type: Option<TileType>,
     tag: Option<TileTag>,
 ) {
-    let index = rng.usize(0..tiles.len());
+    let untagged = |matching: bool| {
+        tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| tile.tag == TileTag::None)
+            .filter(|(_, tile)| !matching || tile_type.map(|t| t == tile.tile_type).unwrap_or(true))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>()
+    };
+    let mut free = untagged(true);
+    if free.is_empty() {
+        free = untagged(false);
+    }
+    if free.is_empty() {
+        bevy::log::warn!("No free tile left to place {:?}", tag);
+        return;
+    }
+    let index = free[rng.usize(0..free.len())];
     if let Some(mut tile) = tiles.get_mut(index) {
         if let Some(tile_type) = tile_type {
             tile.tile_type = tile_type;
@@ -132,3 +451,189 @@ pub(crate) fn random_place_tile<T: DelegatedRng>(
         }
     }
 }
+
+#[cfg(test)]
+impl ScenarioMap {
+    /// Builds a map from rows of symbols - the terrain symbols of authored maps, 'S' the start,
+    /// '1' the goal and 'E' enemies in order
+    pub(crate) fn from_grid(grid: &str) -> ScenarioMap {
+        let rows = grid.trim().lines().collect::<Vec<_>>();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut enemies = 0;
+        let tiles = (0..width)
+            .flat_map(|x| (0..rows.len()).map(move |y| (x, y)))
+            .map(|(x, y)| {
+                let symbol = rows[y].as_bytes().get(x).copied().unwrap_or(b'#');
+                let (tile_type, tag) = match symbol {
+                    b'#' => (TileType::Wall, TileTag::None),
+                    b'o' => (TileType::Obstacle, TileTag::None),
+                    b',' => (TileType::Difficult, TileTag::None),
+                    b'^' => (TileType::Hazard, TileTag::None),
+                    b'+' => (TileType::Spring, TileTag::None),
+                    b'*' => (TileType::Ice, TileTag::None),
+                    b'S' => (TileType::Floor, TileTag::Start),
+                    b'1' => (TileType::Floor, TileTag::Target(0)),
+                    b'E' => {
+                        enemies += 1;
+                        (TileType::Floor, TileTag::Enemy(Actor::Enemy(enemies - 1)))
+                    }
+                    _ => (TileType::Floor, TileTag::None),
+                };
+                Tile {
+                    pos: (x, y),
+                    tile_type,
+                    tag,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        ScenarioMap {
+            width,
+            height: rows.len(),
+            tiles,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_turborand::RngComponent;
+
+    use super::{
+        generators::{MapLayout, MapStyle},
+        *,
+    };
+    use crate::story::{Goal, ScenarioState};
+
+    #[test]
+    fn generated_maps_are_playable() {
+        let enemies = (0..4)
+            .map(|i| (Actor::Enemy(i), ActorResource::default()))
+            .collect::<HashMap<_, _>>();
+        for layout in [MapLayout::Scatter, MapLayout::Rooms, MapLayout::Caves] {
+            let scenario = Scenario {
+                initial_description: String::new(),
+                state: ScenarioState::InProgress(0),
+                goals: vec![Goal::default(); 3],
+                map: MapStyle {
+                    layout,
+                    ..Default::default()
+                },
+            };
+            for seed in 0..500 {
+                let map = ScenarioMap::generate(
+                    &mut RngComponent::with_seed(seed),
+                    &scenario,
+                    &enemies,
+                    &HashMap::default(),
+                );
+                assert!(map.is_connected(), "{layout:?} {seed}: unreachable goal");
+
+                let start = map.start();
+                assert!(start.is_some(), "{layout:?} {seed}: no player spawn");
+
+                let targets = map.targets();
+                let goals = targets.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+                assert_eq!(goals, vec![0, 1, 2], "{layout:?} {seed}: goal tags");
+                for (i, pos) in targets {
+                    assert_eq!(
+                        map.tile(pos).map(|tile| tile.tile_type),
+                        Some(TileType::Floor),
+                        "{layout:?} {seed}: goal {i} isn't on the floor"
+                    );
+                    assert_ne!(Some(pos), start, "{layout:?} {seed}: spawn on goal {i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn terrain_rules_follow_the_tile_type() {
+        let cost = |tile_type: TileType| tile_type.rules().move_cost;
+        assert_eq!(cost(TileType::Floor), Some(1));
+        assert_eq!(cost(TileType::Difficult), Some(2));
+        assert_eq!(cost(TileType::Hazard), Some(1));
+        assert_eq!(cost(TileType::Ice), Some(1));
+        for blocked in [TileType::Empty, TileType::Obstacle, TileType::Wall] {
+            assert_eq!(cost(blocked), None, "{blocked:?}");
+            assert!(!blocked.is_walkable());
+        }
+
+        assert_eq!(
+            TileType::Hazard.rules().end_of_turn,
+            Some(TerrainEffect::Damage(1))
+        );
+        assert_eq!(
+            TileType::Spring.rules().end_of_turn,
+            Some(TerrainEffect::Heal(1))
+        );
+        assert_eq!(TileType::Floor.rules().end_of_turn, None);
+        assert!(TileType::Ice.rules().slippery);
+        assert!(!TileType::Floor.rules().slippery);
+    }
+
+    #[test]
+    fn obstacles_and_walls_break_under_damage() {
+        let mut map = ScenarioMap::from_grid("#####\n#So1#\n#####");
+        assert!(map.is_destructible((2, 1)));
+        assert!(!map.is_destructible((1, 1)));
+
+        assert!(!map.damage_tile((2, 1), 1));
+        assert_eq!(
+            map.tile((2, 1)).map(|tile| tile.tile_type),
+            Some(TileType::Obstacle)
+        );
+        assert!(map.damage_tile((2, 1), 1));
+        assert_eq!(
+            map.tile((2, 1)).map(|tile| tile.tile_type),
+            Some(TileType::Floor)
+        );
+        assert!(!map.is_destructible((2, 1)));
+
+        assert!(!map.damage_tile((0, 1), 3));
+        assert_eq!(
+            map.tile((0, 1)).map(|tile| tile.tile_type),
+            Some(TileType::Wall)
+        );
+        assert!(map.damage_tile((0, 1), 1));
+        assert_eq!(
+            map.tile((0, 1)).map(|tile| tile.tile_type),
+            Some(TileType::Floor)
+        );
+
+        assert!(!map.damage_tile((1, 1), 10));
+    }
+
+    #[test]
+    fn walled_off_goals_get_a_path() {
+        let mut map = ScenarioMap::from_grid("#####\n#S#1#\n#####");
+        assert!(!map.is_connected());
+        assert_eq!(map.unreachable_targets(), vec![0]);
+
+        map.connect_targets();
+        assert!(map.is_connected());
+        assert!(map.unreachable_targets().is_empty());
+    }
+
+    #[test]
+    fn authored_maps_are_repaired() {
+        let definition = MapDefinition {
+            id: "walled".to_string(),
+            grid: "#####\n#S#1#\n#####".to_string(),
+        };
+        let map = ScenarioMap::from_definition(
+            &mut RngComponent::with_seed(0),
+            &definition,
+            &Scenario {
+                initial_description: String::new(),
+                state: ScenarioState::InProgress(0),
+                goals: vec![Goal::default()],
+                map: Default::default(),
+            },
+            &HashMap::default(),
+        )
+        .unwrap();
+        assert!(map.is_connected());
+        assert!(map.unreachable_targets().is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use bevy_turborand::DelegatedRng;
+use serde::{Deserialize, Serialize};
+
+use super::TileType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapLayout {
+    Scatter,
+    Rooms,
+    Caves,
+}
+
+impl MapLayout {
+    pub fn parse(string: &str) -> Option<Self> {
+        match string.trim() {
+            "scatter" => Some(Self::Scatter),
+            "rooms" => Some(Self::Rooms),
+            "caves" => Some(Self::Caves),
+            _ => None,
+        }
+    }
+
+    pub fn generate<T: DelegatedRng>(
+        &self,
+        rng: &mut T,
+        width: usize,
+        height: usize,
+    ) -> Vec<TileType> {
+        let tiles = match self {
+            MapLayout::Scatter => ScatterGenerator.generate(rng, width, height),
+            MapLayout::Rooms => RoomsGenerator.generate(rng, width, height),
+            MapLayout::Caves => CavesGenerator.generate(rng, width, height),
+        };
+        self.add_terrain(rng, tiles)
+    }
+
+    fn terrain(&self) -> &'static [(TileType, f32)] {
+        match self {
+            MapLayout::Scatter => &[
+                (TileType::Difficult, 0.06),
+                (TileType::Hazard, 0.03),
+                (TileType::Spring, 0.02),
+                (TileType::Ice, 0.03),
+            ],
+            MapLayout::Rooms => &[(TileType::Hazard, 0.05), (TileType::Spring, 0.02)],
+            MapLayout::Caves => &[
+                (TileType::Difficult, 0.08),
+                (TileType::Spring, 0.02),
+                (TileType::Ice, 0.1),
+            ],
+        }
+    }
+
+    fn add_terrain<T: DelegatedRng>(&self, rng: &mut T, tiles: Vec<TileType>) -> Vec<TileType> {
+        let terrain = self.terrain();
+        tiles
+            .into_iter()
+            .map(|tile| {
+                if tile != TileType::Floor {
+                    return tile;
+                }
+                let mut roll = rng.f32_normalized();
+                for (terrain, chance) in terrain.iter() {
+                    if roll < *chance {
+                        return *terrain;
+                    }
+                    roll -= chance;
+                }
+                tile
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapStyle {
+    pub layout: MapLayout,
+    pub min_size: usize,
+    pub max_size: usize,
+    #[serde(default)]
+    pub authored: Option<String>,
+}
+
+impl Default for MapStyle {
+    fn default() -> Self {
+        Self {
+            layout: MapLayout::Scatter,
+            min_size: 10,
+            max_size: 20,
+            authored: None,
+        }
+    }
+}
+
+pub trait MapGenerator {
+    fn generate<T: DelegatedRng>(&self, rng: &mut T, width: usize, height: usize) -> Vec<TileType>;
+}
+
+pub struct ScatterGenerator;
+
+impl MapGenerator for ScatterGenerator {
+    fn generate<T: DelegatedRng>(&self, rng: &mut T, width: usize, height: usize) -> Vec<TileType> {
+        (0..width * height)
+            .map(|_| match rng.f32_normalized() {
+                p if p < 0.7 => TileType::Floor,
+                p if p < 0.8 => TileType::Obstacle,
+                p if p < 0.9 => TileType::Wall,
+                _ => TileType::Empty,
+            })
+            .collect()
+    }
+}
+
+pub struct RoomsGenerator;
+
+impl MapGenerator for RoomsGenerator {
+    fn generate<T: DelegatedRng>(&self, rng: &mut T, width: usize, height: usize) -> Vec<TileType> {
+        let mut tiles = vec![TileType::Empty; width * height];
+        let mut centers: Vec<(usize, usize)> = vec![];
+
+        for _ in 0..rng.usize(4..8) {
+            let room_width = rng.usize(3..=6).min(width.saturating_sub(2).max(1));
+            let room_height = rng.usize(3..=6).min(height.saturating_sub(2).max(1));
+            let x = rng.usize(1..=(width - room_width).saturating_sub(1).max(1));
+            let y = rng.usize(1..=(height - room_height).saturating_sub(1).max(1));
+
+            for rx in x..(x + room_width).min(width) {
+                for ry in y..(y + room_height).min(height) {
+                    tiles[rx * height + ry] = if rng.f32_normalized() < 0.08 {
+                        TileType::Obstacle
+                    } else {
+                        TileType::Floor
+                    };
+                }
+            }
+
+            let center = (
+                (x + room_width / 2).min(width - 1),
+                (y + room_height / 2).min(height - 1),
+            );
+            if let Some(previous) = centers.last() {
+                carve_corridor(&mut tiles, height, *previous, center);
+            }
+            centers.push(center);
+        }
+
+        for x in 0..width {
+            for y in 0..height {
+                if tiles[x * height + y] != TileType::Empty {
+                    continue;
+                }
+                let next_to_room = neighbours(x, y, width, height)
+                    .any(|(nx, ny)| tiles[nx * height + ny] != TileType::Empty);
+                if next_to_room {
+                    tiles[x * height + y] = TileType::Wall;
+                }
+            }
+        }
+
+        tiles
+    }
+}
+
+fn carve_corridor(tiles: &mut [TileType], height: usize, from: (usize, usize), to: (usize, usize)) {
+    let (mut x, mut y) = from;
+    while x != to.0 {
+        tiles[x * height + y] = TileType::Floor;
+        x = if x < to.0 { x + 1 } else { x - 1 };
+    }
+    while y != to.1 {
+        tiles[x * height + y] = TileType::Floor;
+        y = if y < to.1 { y + 1 } else { y - 1 };
+    }
+    tiles[x * height + y] = TileType::Floor;
+}
+
+pub struct CavesGenerator;
+
+impl MapGenerator for CavesGenerator {
+    fn generate<T: DelegatedRng>(&self, rng: &mut T, width: usize, height: usize) -> Vec<TileType> {
+        let mut walls = (0..width * height)
+            .map(|_| rng.f32_normalized() < 0.45)
+            .collect::<Vec<_>>();
+
+        for _ in 0..4 {
+            walls = (0..width)
+                .flat_map(|x| (0..height).map(move |y| (x, y)))
+                .map(|(x, y)| {
+                    let missing = 8 - neighbours(x, y, width, height).count();
+                    let wall_count = missing
+                        + neighbours(x, y, width, height)
+                            .filter(|(nx, ny)| walls[nx * height + ny])
+                            .count();
+                    match wall_count {
+                        c if c >= 5 => true,
+                        c if c <= 3 => false,
+                        _ => walls[x * height + y],
+                    }
+                })
+                .collect();
+        }
+
+        walls
+            .into_iter()
+            .map(|wall| {
+                if wall {
+                    TileType::Wall
+                } else if rng.f32_normalized() < 0.05 {
+                    TileType::Obstacle
+                } else {
+                    TileType::Floor
+                }
+            })
+            .collect()
+    }
+}
+
+fn neighbours(
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    (-1i32..=1)
+        .flat_map(|dx| (-1i32..=1).map(move |dy| (dx, dy)))
+        .filter(|offset| *offset != (0, 0))
+        .filter_map(move |(dx, dy)| {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                None
+            } else {
+                Some((nx as usize, ny as usize))
+            }
+        })
+}
@@ -1,98 +1,447 @@
 use bevy::utils::HashSet;
 
-use super::*;
+use super::{
+    pathfinding::{self, DistanceField},
+    *,
+};
 
-use crate::card::Targetable;
+use crate::card::{Card, TargetShape, Targetable};
 
 pub(crate) fn propose_valid_targets(
     actor: &Actor,
     targetable: &Targetable,
     actor_positions: &[(Actor, ActorPosition)],
     map: &scenario_map::ScenarioMap,
-    _resources: &ActorResources,
+    resources: &ActorResources,
 ) -> Vec<(usize, usize)> {
     let my_position = actor_positions
         .iter()
         .find_map(|(a, p)| if a == actor { Some(*p) } else { None });
     match targetable {
         Targetable::Path { max_distance } => {
+            let max_distance = resources
+                .resources
+                .get(actor)
+                .map(|res| res.movement_range(*max_distance))
+                .unwrap_or(*max_distance);
+            match my_position {
+                Some(my_position) if max_distance > 0 => {
+                    let occupied = actor_positions
+                        .iter()
+                        .map(|(_, p)| p.into())
+                        .collect::<HashSet<(usize, usize)>>();
+                    DistanceField::new(map, my_position.into(), &occupied, max_distance).reachable()
+                }
+                _ => vec![],
+            }
+        }
+        Targetable::Creature { max_distance } => my_position
+            .map(|my_position| visible_creatures(map, my_position, actor_positions, *max_distance))
+            .unwrap_or_default(),
+        Targetable::Attackable { max_distance } => {
             if let Some(my_position) = my_position {
-                let occupied = actor_positions
-                    .iter()
-                    .map(|(_, p)| p.into())
-                    .collect::<HashSet<(usize, usize)>>();
-                let positions = map
-                    .tiles
-                    .iter()
-                    .filter_map(|t| {
-                        if t.tile_type == scenario_map::TileType::Floor {
-                            Some(t.pos)
-                        } else {
-                            None
-                        }
-                    })
-                    .filter(|p| !occupied.contains(p))
-                    .collect::<Vec<_>>();
-                positions_within_n(&(my_position.0, my_position.1), &positions, *max_distance)
+                let mut targets =
+                    visible_creatures(map, my_position, actor_positions, *max_distance);
+                targets.extend(
+                    map.tiles
+                        .iter()
+                        .filter(|t| map.is_destructible(t.pos))
+                        .map(|t| t.pos)
+                        .filter(|p| {
+                            p.0.abs_diff(my_position.0) <= *max_distance
+                                && p.1.abs_diff(my_position.1) <= *max_distance
+                                && line_of_sight(map, my_position.into(), *p) != Sight::Blocked
+                        }),
+                );
+                targets
             } else {
                 vec![]
             }
         }
-        Targetable::Creature { max_distance } => {
-            if let Some(my_position) = my_position {
-                actor_positions
+        Targetable::Tile { max_distance } => my_position
+            .map(|my_position| {
+                map.tiles
                     .iter()
-                    .filter_map(|(_a, p)| {
-                        if p.0.abs_diff(my_position.0) <= *max_distance
-                            && p.1.abs_diff(my_position.1) <= *max_distance
-                        {
-                            Some((p.0, p.1))
-                        } else {
-                            None
-                        }
+                    .filter(|t| t.tile_type != scenario_map::TileType::Empty)
+                    .map(|t| t.pos)
+                    .filter(|p| {
+                        within(my_position.into(), *p, *max_distance)
+                            && line_of_sight(map, my_position.into(), *p) != Sight::Blocked
                     })
                     .collect()
-            } else {
-                vec![]
-            }
-        }
+            })
+            .unwrap_or_default(),
+        Targetable::EmptyTile { max_distance } => my_position
+            .map(|my_position| {
+                map.tiles
+                    .iter()
+                    .filter(|t| t.tile_type.is_walkable())
+                    .map(|t| t.pos)
+                    .filter(|p| {
+                        within(my_position.into(), *p, *max_distance)
+                            && !actor_positions.iter().any(|(_, a)| (a.0, a.1) == *p)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Targetable::Caster => my_position.map(|p| vec![p.into()]).unwrap_or_default(),
     }
 }
 
-pub(crate) fn positions_within_n(
-    position: &(usize, usize),
-    positions: &[(usize, usize)],
-    distance: usize,
+fn within(a: (usize, usize), b: (usize, usize), distance: usize) -> bool {
+    a.0.abs_diff(b.0) <= distance && a.1.abs_diff(b.1) <= distance
+}
+
+pub(crate) fn target_selection(
+    actor: &Actor,
+    card: &Card,
+    action_id: usize,
+    actor_positions: &[(Actor, ActorPosition)],
+    map: &scenario_map::ScenarioMap,
+    resources: &ActorResources,
+) -> Option<TargetSelection> {
+    let action = card.actions.get(action_id)?;
+    let valid_targets =
+        propose_valid_targets(actor, &action.target(), actor_positions, map, resources);
+    let origin = actor_positions
+        .iter()
+        .find_map(|(a, p)| if a == actor { Some(p.into()) } else { None });
+    let shape = action.shape();
+    let affected_tiles = valid_targets
+        .iter()
+        .map(|target| {
+            let tiles = origin
+                .map(|origin| shape_tiles(map, origin, *target, &shape))
+                .unwrap_or_else(|| vec![*target]);
+            (*target, tiles)
+        })
+        .collect();
+    Some(TargetSelection {
+        actor: *actor,
+        card: card.clone(),
+        valid_targets,
+        affected_tiles,
+        num_targets_to_select: action.num_targets(),
+        action_id,
+    })
+}
+
+pub(crate) fn affected_tiles(
+    map: &scenario_map::ScenarioMap,
+    origin: Option<(usize, usize)>,
+    targets: &[(usize, usize)],
+    shape: &TargetShape,
 ) -> Vec<(usize, usize)> {
-    let mut checked = vec![];
-    let mut to_check = vec![*position];
-    let mut unchecked = positions
+    let mut tiles = targets
         .iter()
-        .filter_map(|p| if p != position { Some(*p) } else { None })
+        .flat_map(|target| match origin {
+            Some(origin) => shape_tiles(map, origin, *target, shape),
+            None => vec![*target],
+        })
         .collect::<Vec<_>>();
+    tiles.sort();
+    tiles.dedup();
+    tiles
+}
 
-    for _ in 0..distance {
-        let mut remove = vec![];
-        let mut next_check = vec![];
-        for pos in to_check.iter() {
-            for (i, p) in unchecked.iter().enumerate() {
-                if p.0.abs_diff(pos.0) <= 1 && p.1.abs_diff(pos.1) <= 1 {
-                    remove.push(i);
-                    if !checked.contains(p) {
-                        next_check.push(*p);
+pub(crate) fn shape_tiles(
+    map: &scenario_map::ScenarioMap,
+    origin: (usize, usize),
+    target: (usize, usize),
+    shape: &TargetShape,
+) -> Vec<(usize, usize)> {
+    match shape {
+        TargetShape::Single | TargetShape::Multi { count: _ } => vec![target],
+        TargetShape::Burst { radius } => map
+            .tiles
+            .iter()
+            .filter(|t| t.tile_type != scenario_map::TileType::Empty)
+            .map(|t| t.pos)
+            .filter(|p| {
+                within(target, *p, *radius) && line_of_sight(map, target, *p) != Sight::Blocked
+            })
+            .collect(),
+        TargetShape::Line { length } => {
+            let direction = pathfinding::direction(origin, target);
+            let mut tiles = vec![];
+            let mut current = (origin.0 as i64, origin.1 as i64);
+            if direction == (0, 0) {
+                return tiles;
+            }
+            for _ in 0..*length {
+                current = (current.0 + direction.0, current.1 + direction.1);
+                if current.0 < 0 || current.1 < 0 {
+                    break;
+                }
+                let pos = (current.0 as usize, current.1 as usize);
+                match map.tile(pos).map(|tile| tile.tile_type) {
+                    None | Some(scenario_map::TileType::Empty) => break,
+                    Some(scenario_map::TileType::Wall) => {
+                        tiles.push(pos);
+                        break;
                     }
+                    Some(_) => tiles.push(pos),
                 }
             }
+            tiles
+        }
+        TargetShape::Cone { length } => {
+            let direction = pathfinding::direction(origin, target);
+            if direction == (0, 0) {
+                return vec![];
+            }
+            let direction_length =
+                ((direction.0 * direction.0 + direction.1 * direction.1) as f32).sqrt();
+            map.tiles
+                .iter()
+                .filter(|t| t.tile_type != scenario_map::TileType::Empty)
+                .map(|t| t.pos)
+                .filter(|p| {
+                    let delta = (p.0 as i64 - origin.0 as i64, p.1 as i64 - origin.1 as i64);
+                    if delta == (0, 0) || !within(origin, *p, *length) {
+                        return false;
+                    }
+                    let delta_length = ((delta.0 * delta.0 + delta.1 * delta.1) as f32).sqrt();
+                    let dot = (delta.0 * direction.0 + delta.1 * direction.1) as f32;
+                    dot / (delta_length * direction_length)
+                        >= std::f32::consts::FRAC_1_SQRT_2 - 0.01
+                        && line_of_sight(map, origin, *p) != Sight::Blocked
+                })
+                .collect()
         }
-        unchecked = unchecked
-            .iter()
-            .enumerate()
-            .filter_map(|(i, v)| if remove.contains(&i) { None } else { Some(*v) })
-            .collect();
-        checked.append(&mut to_check);
-        to_check.append(&mut next_check);
     }
+}
 
-    checked.append(&mut to_check);
-    checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sight {
+    Clear,
+    Cover,
+    Blocked,
+}
+
+pub fn line_of_sight(
+    map: &scenario_map::ScenarioMap,
+    from: (usize, usize),
+    to: (usize, usize),
+) -> Sight {
+    let mut sight = Sight::Clear;
+    for pos in line_between(from, to) {
+        match map.tile(pos).map(|tile| tile.tile_type) {
+            Some(scenario_map::TileType::Wall) => return Sight::Blocked,
+            Some(scenario_map::TileType::Obstacle) => sight = Sight::Cover,
+            _ => {}
+        }
+    }
+    sight
+}
+
+pub fn damage_through_cover(
+    map: &scenario_map::ScenarioMap,
+    from: (usize, usize),
+    to: (usize, usize),
+    damage: usize,
+) -> usize {
+    match line_of_sight(map, from, to) {
+        Sight::Clear => damage,
+        Sight::Cover => damage.saturating_sub(1).max(damage.min(1)),
+        Sight::Blocked => 0,
+    }
+}
+
+fn line_between(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (x0, y0) = (from.0 as i64, from.1 as i64);
+    let (x1, y1) = (to.0 as i64, to.1 as i64);
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+    let mut line = vec![];
+
+    while (x, y) != (x1, y1) {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+        if (x, y) != (x1, y1) {
+            line.push((x as usize, y as usize));
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::scenario::{scenario_map::ScenarioMap, simulation};
+
+    #[test]
+    fn walls_block_targeting() {
+        let map = ScenarioMap::from_grid(
+            "
+#######
+#S.#.E#
+#....E#
+#######
+",
+        );
+        let targets = propose_valid_targets(
+            &Actor::Player,
+            &Targetable::Creature { max_distance: 5 },
+            &simulation::start_positions(&map),
+            &map,
+            &ActorResources::default(),
+        );
+
+        assert!(!targets.contains(&(5, 1)));
+        assert!(targets.contains(&(5, 2)));
+        assert_eq!(line_of_sight(&map, (1, 1), (5, 1)), Sight::Blocked);
+    }
+
+    #[test]
+    fn obstacles_reduce_damage() {
+        let map = ScenarioMap::from_grid(
+            "
+######
+#SoE.#
+#..E.#
+######
+",
+        );
+
+        assert_eq!(line_of_sight(&map, (1, 1), (3, 1)), Sight::Cover);
+        assert_eq!(damage_through_cover(&map, (1, 1), (3, 1), 3), 2);
+        assert_eq!(damage_through_cover(&map, (1, 1), (3, 1), 1), 1);
+        assert_eq!(line_of_sight(&map, (1, 2), (3, 2)), Sight::Clear);
+        assert_eq!(damage_through_cover(&map, (1, 2), (3, 2), 3), 3);
+    }
+
+    const OPEN: &str = "
+.......
+.......
+.......
+.......
+.......
+.......
+.......
+";
+
+    const FACINGS: [(i64, i64); 8] = [
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+    ];
+
+    fn step(from: (usize, usize), facing: (i64, i64), distance: i64) -> (usize, usize) {
+        (
+            (from.0 as i64 + facing.0 * distance) as usize,
+            (from.1 as i64 + facing.1 * distance) as usize,
+        )
+    }
+
+    fn shape(
+        map: &ScenarioMap,
+        origin: (usize, usize),
+        target: (usize, usize),
+        shape: TargetShape,
+    ) -> Vec<(usize, usize)> {
+        affected_tiles(map, Some(origin), &[target], &shape)
+    }
+
+    #[test]
+    fn lines_and_cones_turn_with_every_facing() {
+        let map = ScenarioMap::from_grid(OPEN);
+        let origin = (3, 3);
+        for facing in FACINGS {
+            let target = step(origin, facing, 1);
+            let mut line = (1..=3).map(|i| step(origin, facing, i)).collect::<Vec<_>>();
+            line.sort();
+            assert_eq!(
+                shape(&map, origin, target, TargetShape::Line { length: 3 }),
+                line,
+                "{facing:?}"
+            );
+
+            let cone = shape(&map, origin, target, TargetShape::Cone { length: 2 });
+            assert_eq!(cone.len(), 8, "{facing:?}");
+            assert!(cone.contains(&step(origin, facing, 2)), "{facing:?}");
+            for tile in cone {
+                let delta = (tile.0 as i64 - 3, tile.1 as i64 - 3);
+                assert!(
+                    delta.0 * facing.0 + delta.1 * facing.1 > 0,
+                    "{facing:?} {tile:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shapes_are_cut_off_at_the_board_edge() {
+        let map = ScenarioMap::from_grid(OPEN);
+
+        assert!(shape(&map, (0, 3), (0, 3), TargetShape::Line { length: 3 }).is_empty());
+        assert_eq!(
+            shape(&map, (0, 3), (0, 2), TargetShape::Line { length: 9 }),
+            vec![(0, 0), (0, 1), (0, 2)]
+        );
+        assert_eq!(
+            shape(&map, (1, 3), (0, 3), TargetShape::Cone { length: 2 }),
+            vec![(0, 2), (0, 3), (0, 4)]
+        );
+        assert_eq!(
+            shape(&map, (0, 3), (0, 2), TargetShape::Cone { length: 2 }).len(),
+            5
+        );
+        assert_eq!(
+            shape(&map, (1, 1), (0, 0), TargetShape::Burst { radius: 1 }),
+            vec![(0, 0), (0, 1), (1, 0), (1, 1)]
+        );
+        assert_eq!(
+            shape(&map, (3, 3), (3, 3), TargetShape::Burst { radius: 1 }).len(),
+            9
+        );
+    }
+
+    #[test]
+    fn walls_stop_lines_and_shelter_bursts() {
+        let map = ScenarioMap::from_grid(
+            "
+#######
+#S..#.#
+#######
+",
+        );
+
+        assert_eq!(
+            shape(&map, (1, 1), (2, 1), TargetShape::Line { length: 5 }),
+            vec![(2, 1), (3, 1), (4, 1)]
+        );
+        let burst = shape(&map, (1, 1), (3, 1), TargetShape::Burst { radius: 2 });
+        assert!(burst.contains(&(4, 1)));
+        assert!(!burst.contains(&(5, 1)));
+    }
+
+    #[test]
+    fn multi_targets_hit_only_the_chosen_tiles() {
+        let map = ScenarioMap::from_grid(OPEN);
+        let multi = TargetShape::Multi { count: 3 };
+
+        assert_eq!(multi.num_targets(), 3);
+        assert_eq!(TargetShape::Multi { count: 0 }.num_targets(), 1);
+        assert_eq!(
+            affected_tiles(&map, Some((3, 3)), &[(5, 5), (0, 0), (5, 5)], &multi),
+            vec![(0, 0), (5, 5)]
+        );
+    }
 }
@@ -0,0 +1,1647 @@
+use bevy::utils::{HashMap, HashSet};
+use bevy_turborand::{DelegatedRng, RngComponent};
+
+use super::{
+    card_flow::{self, FlowContext, NextStep},
+    enemy_ai::{self, BattleView},
+    pathfinding,
+    scenario_map::{ScenarioMap, TerrainEffect, TileTag},
+    scenario_utilities,
+    status::{Status, StatusKind, TickPhase},
+    types::*,
+};
+use crate::{
+    card::{Bonus, Card, CardAction, Targetable},
+    enemy::EnemyDefinition,
+    map::MapDefinition,
+    run_seed::{RunRngs, RunSeed},
+    story::{GoalType, Scenario, ScenarioState},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnStart {
+    Ready,
+    Stunned,
+    Defeated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BattleOutcome {
+    Victory,
+    Defeat,
+    Timeout,
+}
+
+pub fn generate_enemies<T: DelegatedRng>(
+    rng: &mut T,
+    enemies: &HashMap<String, EnemyDefinition>,
+    cards: &HashMap<String, Card>,
+    tier: usize,
+) -> HashMap<Actor, ActorResource> {
+    let mut eligible = enemies
+        .values()
+        .filter(|enemy| enemy.tier <= tier)
+        .collect::<Vec<_>>();
+    eligible.sort_by(|a, b| a.id.cmp(&b.id));
+
+    if eligible.is_empty() {
+        return generate_fallback_enemies(rng, cards);
+    }
+
+    (0..rng.usize(3..5))
+        .map(|i| {
+            let enemy = eligible[rng.usize(0..eligible.len())];
+            (
+                Actor::Enemy(i),
+                ActorResource {
+                    health: enemy.health,
+                    max_health: enemy.health,
+                    max_energy: enemy.energy,
+                    enemy: Some(enemy.id.clone()),
+                    ai: enemy.ai,
+                    ..Default::default()
+                }
+                .with_deck(playable_deck(enemy, cards), rng.u64(..)),
+            )
+        })
+        .collect()
+}
+
+// An enemy only keeps the cards that exist, so a stale definition can't put an unknown id in a hand
+fn playable_deck(enemy: &EnemyDefinition, cards: &HashMap<String, Card>) -> Vec<String> {
+    enemy
+        .deck
+        .iter()
+        .filter(|id| cards.contains_key(*id))
+        .cloned()
+        .collect()
+}
+
+fn generate_fallback_enemies<T: DelegatedRng>(
+    rng: &mut T,
+    cards: &HashMap<String, Card>,
+) -> HashMap<Actor, ActorResource> {
+    bevy::log::warn!("No enemy definitions available - using default enemies");
+    let mut ids = cards.keys().cloned().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut selected = Vec::with_capacity(3);
+    while selected.len() < ids.len().min(3) {
+        let next = rng.usize(0..ids.len());
+        if !selected.contains(&next) {
+            selected.push(next);
+        }
+    }
+
+    let deck = selected
+        .iter()
+        .filter_map(|i| ids.get(*i))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    (0..rng.usize(3..5))
+        .map(|i| {
+            (
+                Actor::Enemy(i),
+                ActorResource {
+                    health: 2,
+                    max_health: 2,
+                    max_energy: 1,
+                    ..Default::default()
+                }
+                .with_deck(deck.clone(), rng.u64(..)),
+            )
+        })
+        .collect()
+}
+
+pub fn turn_order(resources: &HashMap<Actor, ActorResource>) -> Vec<Actor> {
+    let mut turn_order = resources.keys().copied().collect::<Vec<_>>();
+    if !turn_order.contains(&Actor::Player) {
+        turn_order.push(Actor::Player);
+    }
+    turn_order.sort();
+    turn_order
+}
+
+pub fn start_positions(map: &ScenarioMap) -> Vec<(Actor, ActorPosition)> {
+    map.tiles
+        .iter()
+        .filter_map(|tile| match tile.tag {
+            TileTag::Start => Some((Actor::Player, ActorPosition(tile.pos.0, tile.pos.1))),
+            TileTag::Enemy(actor) => Some((actor, ActorPosition(tile.pos.0, tile.pos.1))),
+            _ => None,
+        })
+        .collect()
+}
+
+pub const MAX_CARDS_PER_TURN: usize = 5;
+
+pub fn can_play(resources: &ActorResources, actor: &Actor, card: &Card) -> bool {
+    resources
+        .resources
+        .get(actor)
+        .map(|res| {
+            res.cards_played < MAX_CARDS_PER_TURN
+                && card.cost <= res.energy
+                && res.hand.contains(&card.id)
+        })
+        .unwrap_or(false)
+}
+
+pub fn playable_cards(
+    resources: &ActorResources,
+    actor: &Actor,
+    cards: &HashMap<String, Card>,
+) -> Vec<String> {
+    resources
+        .resources
+        .get(actor)
+        .map(|res| {
+            res.hand
+                .iter()
+                .filter(|id| {
+                    cards
+                        .get(*id)
+                        .map(|card| can_play(resources, actor, card))
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn play_card(resources: &mut ActorResources, actor: &Actor, card: &Card) -> bool {
+    if !can_play(resources, actor, card) {
+        return false;
+    }
+    match resources.resources.get_mut(actor) {
+        Some(actor_resources) if actor_resources.play_from_hand(card) => {
+            actor_resources.energy -= card.cost;
+            actor_resources.cards_played += 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+pub fn start_turn(resources: &mut ActorResources, actor: &Actor) -> TurnStart {
+    let (stunned, damage) = match resources.resources.get_mut(actor) {
+        Some(res) => {
+            res.energy = res.max_energy;
+            res.cards_played = 0;
+            res.draw_to_hand_size();
+            (
+                res.has_status(StatusKind::Stun),
+                res.tick_statuses(TickPhase::TurnStart),
+            )
+        }
+        None => return TurnStart::Ready,
+    };
+    if damage > 0 {
+        apply_adjustment(resources, &AdjustActorEvent::Damage(*actor, damage));
+        if !resources.turn_order.contains(actor) {
+            return TurnStart::Defeated;
+        }
+    }
+    if stunned {
+        TurnStart::Stunned
+    } else {
+        TurnStart::Ready
+    }
+}
+
+pub fn end_turn(resources: &mut ActorResources, actor: &Actor) {
+    let damage = match resources.resources.get_mut(actor) {
+        Some(res) if res.health > 0 => {
+            res.discard_hand();
+            res.tick_statuses(TickPhase::TurnEnd)
+        }
+        _ => return,
+    };
+    if damage > 0 {
+        apply_adjustment(resources, &AdjustActorEvent::Damage(*actor, damage));
+    }
+}
+
+pub fn next_actor(resources: &ActorResources, actor: &Actor) -> Actor {
+    // The current actor may have died during its own turn and already left the turn order,
+    // so pick the next living actor after it rather than looking up its position
+    let turn_order = &resources.turn_order;
+    turn_order
+        .iter()
+        .filter(|a| *a > actor)
+        .min()
+        .or_else(|| turn_order.iter().min())
+        .copied()
+        .unwrap_or(Actor::Player)
+}
+
+pub fn apply_adjustment(resources: &mut ActorResources, event: &AdjustActorEvent) {
+    match event {
+        AdjustActorEvent::Damage(actor, damage) => {
+            if let Some(res) = resources.resources.get_mut(actor) {
+                let damage = res.incoming_damage(*damage);
+                res.health = res.health.saturating_sub(damage);
+                bevy::log::info!("{} Damage Applied to {:?}", damage, &actor);
+                if res.health == 0 {
+                    bevy::log::info!("{:?} is Dead!", &actor);
+                    resources.turn_order.retain(|a| a != actor);
+                }
+            }
+        }
+        AdjustActorEvent::Heal(actor, amount) => {
+            if let Some(res) = resources.resources.get_mut(actor) {
+                if res.health > 0 {
+                    res.health = (res.health + amount).min(res.max_health);
+                }
+            }
+        }
+        AdjustActorEvent::ApplyStatus(actor, status) => {
+            if let Some(res) = resources.resources.get_mut(actor) {
+                if res.health > 0 {
+                    res.add_status(*status);
+                }
+            }
+        }
+        AdjustActorEvent::Draw(actor, count) => {
+            if let Some(res) = resources.resources.get_mut(actor) {
+                res.draw(*count);
+            }
+        }
+        AdjustActorEvent::Summon(actor, _, resource) => {
+            bevy::log::info!("{:?} summoned", actor);
+            resources.resources.insert(*actor, resource.clone());
+            if !resources.turn_order.contains(actor) {
+                resources.turn_order.push(*actor);
+            }
+        }
+    }
+}
+
+pub fn terrain_effect(
+    map: &ScenarioMap,
+    positions: &[(Actor, ActorPosition)],
+    actor: &Actor,
+) -> Option<AdjustActorEvent> {
+    let position = positions
+        .iter()
+        .find_map(|(a, p)| if a == actor { Some(p.into()) } else { None })?;
+    match map.rules(position)?.end_of_turn? {
+        TerrainEffect::Damage(damage) => Some(AdjustActorEvent::Damage(*actor, damage)),
+        TerrainEffect::Heal(amount) => Some(AdjustActorEvent::Heal(*actor, amount)),
+    }
+}
+
+pub fn goal_reached(
+    scenario: &Scenario,
+    goal_id: usize,
+    map: &ScenarioMap,
+    positions: &[(Actor, ActorPosition)],
+) -> bool {
+    match scenario.goals.get(goal_id).map(|goal| &goal.goal_type) {
+        Some(GoalType::ReachLocation(_)) => {
+            let target = map.tiles.iter().find_map(|tile| match tile.tag {
+                TileTag::Target(i) if i == goal_id => Some(tile.pos),
+                _ => None,
+            });
+            let player_pos = positions.iter().find_map(|(a, p)| match a {
+                Actor::Player => Some((p.0, p.1)),
+                Actor::Enemy(_) => None,
+            });
+            bevy::log::info!("Checking player success {:?} - {:?}", target, player_pos);
+            target.is_some() && target == player_pos
+        }
+        None => false,
+    }
+}
+
+pub fn player_failed(resources: &ActorResources) -> bool {
+    resources
+        .resources
+        .get(&Actor::Player)
+        .map(|player| player.health == 0)
+        .unwrap_or(false)
+}
+
+pub fn resolve_action(
+    actor: &Actor,
+    action: &CardAction,
+    targets: &[(usize, usize)],
+    map: &mut ScenarioMap,
+    enemies: &HashMap<String, EnemyDefinition>,
+    cards: &HashMap<String, Card>,
+    positions: &mut Vec<(Actor, ActorPosition)>,
+    resources: &mut ActorResources,
+) -> Vec<AnimateActionsEvents> {
+    let mut resolved = vec![];
+    let origin: Option<(usize, usize)> =
+        positions
+            .iter()
+            .find_map(|(a, p)| if a == actor { Some(p.into()) } else { None });
+    if let Some(target) = targets.first() {
+        match action {
+            CardAction::Move(_) => {
+                let occupied = positions
+                    .iter()
+                    .filter(|(a, _)| a != actor)
+                    .map(|(_, p)| p.into())
+                    .collect::<HashSet<(usize, usize)>>();
+                let path = origin
+                    .and_then(|origin| pathfinding::movement_path(map, origin, *target, &occupied));
+                match path {
+                    Some(path) if !path.is_empty() => {
+                        if let (Some((_, position)), Some(destination)) =
+                            (positions.iter_mut().find(|(a, _)| a == actor), path.last())
+                        {
+                            *position = ActorPosition(destination.0, destination.1);
+                        }
+                        resolved.push(AnimateActionsEvents::Move(*actor, to_positions(path)));
+                    }
+                    _ => bevy::log::info!("{:?} can't reach {:?}", actor, target),
+                }
+            }
+            CardAction::Attack {
+                damage,
+                range: _,
+                shape,
+                bonus,
+            } if shape.is_area() => {
+                let bonus = bonus_damage(bonus, actor, positions, resources, targets);
+                let damage = outgoing_damage(resources, actor, *damage + bonus);
+                let mut impact = Impact::default();
+                for tile in scenario_utilities::affected_tiles(map, origin, targets, shape).iter() {
+                    adjust_actors_at(tile, positions, resources, &mut impact, |a| {
+                        (a != *actor).then_some(AdjustActorEvent::Damage(a, damage))
+                    });
+                    damage_tile(map, *tile, damage, &mut impact);
+                }
+                resolved.push(AnimateActionsEvents::AreaAttack(*actor, impact));
+            }
+            CardAction::Attack {
+                damage,
+                range: _,
+                shape: _,
+                bonus,
+            } => {
+                for target in targets.iter() {
+                    let bonus = bonus_damage(
+                        bonus,
+                        actor,
+                        positions,
+                        resources,
+                        std::slice::from_ref(target),
+                    );
+                    let damage =
+                        attack_damage(map, resources, actor, origin, *target, *damage + bonus);
+                    let mut impact = Impact::default();
+                    adjust_actors_at(target, positions, resources, &mut impact, |a| {
+                        (a != *actor).then_some(AdjustActorEvent::Damage(a, damage))
+                    });
+                    damage_tile(map, *target, damage, &mut impact);
+                    resolved.push(AnimateActionsEvents::Attack(*actor, impact));
+                }
+            }
+            CardAction::Stun {
+                range: _,
+                duration,
+                shape,
+            } => {
+                let status = Status::new(StatusKind::Stun, *duration);
+                let mut impact = Impact::default();
+                for tile in scenario_utilities::affected_tiles(map, origin, targets, shape).iter() {
+                    adjust_actors_at(tile, positions, resources, &mut impact, |a| {
+                        status
+                            .affects(actor, &a)
+                            .then_some(AdjustActorEvent::ApplyStatus(a, status))
+                    });
+                }
+                resolved.push(AnimateActionsEvents::ApplyStatus(*actor, impact));
+            }
+            CardAction::ApplyStatus {
+                range: _,
+                status,
+                shape,
+            } => {
+                let mut impact = Impact::default();
+                for tile in scenario_utilities::affected_tiles(map, origin, targets, shape).iter() {
+                    adjust_actors_at(tile, positions, resources, &mut impact, |a| {
+                        status
+                            .affects(actor, &a)
+                            .then_some(AdjustActorEvent::ApplyStatus(a, *status))
+                    });
+                }
+                resolved.push(AnimateActionsEvents::ApplyStatus(*actor, impact));
+            }
+            CardAction::Heal {
+                amount,
+                range: _,
+                shape,
+            } => {
+                let mut impact = Impact::default();
+                for tile in scenario_utilities::affected_tiles(map, origin, targets, shape).iter() {
+                    adjust_actors_at(tile, positions, resources, &mut impact, |a| {
+                        Some(AdjustActorEvent::Heal(a, *amount))
+                    });
+                }
+                resolved.push(AnimateActionsEvents::Heal(*actor, impact));
+            }
+            CardAction::Push {
+                distance: _,
+                range: _,
+            }
+            | CardAction::Pull {
+                distance: _,
+                range: _,
+            } => {
+                if let Some((pushed, path)) =
+                    forced_movement(map, positions, actor, *target, action)
+                {
+                    if let (Some((_, position)), Some(destination)) = (
+                        positions.iter_mut().find(|(a, _)| *a == pushed),
+                        path.last(),
+                    ) {
+                        *position = ActorPosition(destination.0, destination.1);
+                    }
+                    resolved.push(AnimateActionsEvents::Push(pushed, to_positions(path)));
+                }
+            }
+            CardAction::Teleport { range: _ } => {
+                let destination = ActorPosition(target.0, target.1);
+                if let Some((_, position)) = positions.iter_mut().find(|(a, _)| a == actor) {
+                    *position = destination;
+                }
+                resolved.push(AnimateActionsEvents::Teleport(*actor, destination));
+            }
+            CardAction::Draw(count) => {
+                let mut impact = Impact::default();
+                adjust(
+                    resources,
+                    &mut impact,
+                    AdjustActorEvent::Draw(*actor, *count),
+                );
+                resolved.push(AnimateActionsEvents::Draw(*actor, impact));
+            }
+            CardAction::Block(amount) => {
+                let mut impact = Impact::default();
+                adjust(
+                    resources,
+                    &mut impact,
+                    AdjustActorEvent::ApplyStatus(*actor, Status::block(*amount)),
+                );
+                resolved.push(AnimateActionsEvents::ApplyStatus(*actor, impact));
+            }
+            CardAction::Summon { enemy, range: _ } => {
+                if let Some((summoned, resource)) = summon(resources, enemies, cards, actor, enemy)
+                {
+                    let position = ActorPosition(target.0, target.1);
+                    let mut impact = Impact::default();
+                    adjust(
+                        resources,
+                        &mut impact,
+                        AdjustActorEvent::Summon(summoned, position, resource),
+                    );
+                    positions.push((summoned, position));
+                    resolved.push(AnimateActionsEvents::Summon(*actor, impact));
+                }
+            }
+            CardAction::If {
+                condition: _,
+                then: _,
+                otherwise: _,
+            }
+            | CardAction::Choose(_)
+            | CardAction::Repeat {
+                times: _,
+                actions: _,
+            } => {
+                bevy::log::warn!("Control flow actions are expanded before they resolve");
+            }
+        }
+    }
+    resolved
+}
+
+pub fn bonus_damage(
+    bonus: &[Bonus],
+    actor: &Actor,
+    positions: &[(Actor, ActorPosition)],
+    resources: &ActorResources,
+    targets: &[(usize, usize)],
+) -> usize {
+    card_flow::bonus_damage(
+        bonus,
+        &FlowContext {
+            actor: *actor,
+            positions,
+            resources,
+            targets,
+        },
+    )
+}
+
+pub fn forced_movement(
+    map: &ScenarioMap,
+    positions: &[(Actor, ActorPosition)],
+    actor: &Actor,
+    target: (usize, usize),
+    action: &CardAction,
+) -> Option<(Actor, Vec<(usize, usize)>)> {
+    let origin = positions
+        .iter()
+        .find_map(|(a, p)| if a == actor { Some(p.into()) } else { None })?;
+    let moved = positions
+        .iter()
+        .find(|(a, p)| a != actor && (p.0, p.1) == target)
+        .map(|(a, _)| *a)?;
+    let (direction, distance) = match action {
+        CardAction::Push { distance, range: _ } => {
+            (pathfinding::direction(origin, target), *distance)
+        }
+        CardAction::Pull { distance, range: _ } => {
+            (pathfinding::direction(target, origin), *distance)
+        }
+        _ => return None,
+    };
+    let occupied = positions
+        .iter()
+        .filter(|(a, _)| *a != moved)
+        .map(|(_, p)| p.into())
+        .collect::<HashSet<(usize, usize)>>();
+    let path = pathfinding::forced_path(map, target, direction, distance, &occupied);
+    if path.is_empty() {
+        None
+    } else {
+        Some((moved, path))
+    }
+}
+
+pub fn summon(
+    resources: &mut ActorResources,
+    enemies: &HashMap<String, EnemyDefinition>,
+    cards: &HashMap<String, Card>,
+    actor: &Actor,
+    enemy: &str,
+) -> Option<(Actor, ActorResource)> {
+    if *actor == Actor::Player {
+        bevy::log::warn!("Only enemies can summon allies");
+        return None;
+    }
+    let definition = enemies.get(enemy)?;
+    let next = resources
+        .resources
+        .keys()
+        .filter_map(|a| match a {
+            Actor::Enemy(i) => Some(i + 1),
+            Actor::Player => None,
+        })
+        .max()
+        .unwrap_or(0);
+    // Shuffle the summoned deck from the summoner's stream, which the enemy hands stream seeded
+    let shuffle_seed = resources.resources.get_mut(actor)?.next_shuffle_seed();
+    Some((
+        Actor::Enemy(next),
+        ActorResource {
+            health: definition.health,
+            max_health: definition.health,
+            max_energy: definition.energy,
+            enemy: Some(definition.id.clone()),
+            ai: definition.ai,
+            ..Default::default()
+        }
+        .with_deck(playable_deck(definition, cards), shuffle_seed),
+    ))
+}
+
+pub fn apply_tile_adjustment(map: &mut ScenarioMap, event: &AdjustTileEvent) -> bool {
+    match event {
+        AdjustTileEvent::Damage(pos, damage) => {
+            let destroyed = map.damage_tile(*pos, *damage);
+            if destroyed {
+                bevy::log::info!("Tile {:?} destroyed", pos);
+            }
+            destroyed
+        }
+    }
+}
+
+pub fn attack_damage(
+    map: &ScenarioMap,
+    resources: &ActorResources,
+    actor: &Actor,
+    origin: Option<(usize, usize)>,
+    target: (usize, usize),
+    damage: usize,
+) -> usize {
+    let damage = origin
+        .map(|origin| scenario_utilities::damage_through_cover(map, origin, target, damage))
+        .unwrap_or(damage);
+    outgoing_damage(resources, actor, damage)
+}
+
+pub fn outgoing_damage(resources: &ActorResources, actor: &Actor, damage: usize) -> usize {
+    resources
+        .resources
+        .get(actor)
+        .map(|res| res.outgoing_damage(damage))
+        .unwrap_or(damage)
+}
+
+fn to_positions(path: Vec<(usize, usize)>) -> Vec<ActorPosition> {
+    path.into_iter().map(|(x, y)| ActorPosition(x, y)).collect()
+}
+
+fn adjust(resources: &mut ActorResources, impact: &mut Impact, event: AdjustActorEvent) {
+    apply_adjustment(resources, &event);
+    impact.actors.push(event);
+}
+
+fn adjust_actors_at<F: Fn(Actor) -> Option<AdjustActorEvent>>(
+    target: &(usize, usize),
+    positions: &mut Vec<(Actor, ActorPosition)>,
+    resources: &mut ActorResources,
+    impact: &mut Impact,
+    event: F,
+) {
+    let hit = positions
+        .iter()
+        .filter(|(_, p)| (p.0, p.1) == *target)
+        .filter_map(|(a, _)| event(*a))
+        .collect::<Vec<_>>();
+    for event in hit {
+        adjust(resources, impact, event);
+    }
+    positions.retain(|(a, _)| resources.turn_order.contains(a));
+}
+
+fn damage_tile(map: &mut ScenarioMap, tile: (usize, usize), damage: usize, impact: &mut Impact) {
+    if map.is_destructible(tile) {
+        let event = AdjustTileEvent::Damage(tile, damage);
+        apply_tile_adjustment(map, &event);
+        impact.tiles.push(event);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Battle {
+    pub scenario: Scenario,
+    pub map: ScenarioMap,
+    pub positions: Vec<(Actor, ActorPosition)>,
+    pub resources: ActorResources,
+    pub cards: HashMap<String, Card>,
+    pub enemies: HashMap<String, EnemyDefinition>,
+    pub turns_taken: usize,
+    rng: RngComponent,
+}
+
+impl Battle {
+    pub fn new(
+        scenario: Scenario,
+        map: ScenarioMap,
+        resources: ActorResources,
+        cards: HashMap<String, Card>,
+        enemies: HashMap<String, EnemyDefinition>,
+        seed: u64,
+    ) -> Self {
+        Self {
+            scenario,
+            positions: start_positions(&map),
+            map,
+            resources,
+            cards,
+            enemies,
+            turns_taken: 0,
+            rng: RngComponent::with_seed(seed),
+        }
+    }
+
+    pub fn setup(
+        scenario: Scenario,
+        cards: HashMap<String, Card>,
+        enemies: &HashMap<String, EnemyDefinition>,
+        maps: &HashMap<String, MapDefinition>,
+        enemy_tier: usize,
+        player: ActorResource,
+        seed: u64,
+    ) -> Self {
+        let mut rngs = RunRngs::new(RunSeed(seed));
+        let mut resources = generate_enemies(&mut rngs.enemy_hands, enemies, &cards, enemy_tier);
+        let map = ScenarioMap::generate(&mut rngs.map, &scenario, &resources, maps);
+        let turn_order = turn_order(&resources);
+        resources.insert(Actor::Player, player);
+        Self {
+            scenario,
+            positions: start_positions(&map),
+            map,
+            resources: ActorResources {
+                resources,
+                turn_order,
+            },
+            cards,
+            enemies: enemies.clone(),
+            turns_taken: 0,
+            rng: rngs.enemy_ai,
+        }
+    }
+
+    pub fn run(&mut self, max_turns: usize) -> BattleOutcome {
+        let mut actor = self
+            .resources
+            .turn_order
+            .first()
+            .copied()
+            .unwrap_or(Actor::Player);
+        while self.turns_taken < max_turns {
+            if let Some(outcome) = self.take_turn(actor) {
+                return outcome;
+            }
+            actor = next_actor(&self.resources, &actor);
+        }
+        BattleOutcome::Timeout
+    }
+
+    pub fn take_turn(&mut self, actor: Actor) -> Option<BattleOutcome> {
+        self.turns_taken += 1;
+        if start_turn(&mut self.resources, &actor) == TurnStart::Ready {
+            while let Some(card) = self.choose_card(&actor) {
+                if !play_card(&mut self.resources, &actor, &card) {
+                    break;
+                }
+                self.play(&actor, card);
+                if !self.resources.turn_order.contains(&actor) {
+                    break;
+                }
+            }
+        }
+        if let Some(effect) = terrain_effect(&self.map, &self.positions, &actor) {
+            apply_adjustment(&mut self.resources, &effect);
+        }
+        end_turn(&mut self.resources, &actor);
+        let turn_order = &self.resources.turn_order;
+        self.positions.retain(|(a, _)| turn_order.contains(a));
+        self.check_outcome()
+    }
+
+    fn choose_card(&mut self, actor: &Actor) -> Option<Card> {
+        match actor {
+            Actor::Player => {
+                let playable = playable_cards(&self.resources, actor, &self.cards);
+                enemy_ai::choose_card(&mut self.rng, &playable)
+            }
+            Actor::Enemy(_) => {
+                let view = BattleView {
+                    map: &self.map,
+                    positions: &self.positions,
+                    resources: &self.resources,
+                    cards: &self.cards,
+                    enemies: &self.enemies,
+                };
+                enemy_ai::policy_for(&self.resources, actor).choose_card(
+                    &mut self.rng,
+                    &view,
+                    actor,
+                )
+            }
+        }
+        .and_then(|id| self.cards.get(&id).cloned())
+    }
+
+    fn play(&mut self, actor: &Actor, mut card: Card) {
+        let mut index = 0;
+        let mut targets = vec![];
+        loop {
+            let ctx = FlowContext {
+                actor: *actor,
+                positions: &self.positions,
+                resources: &self.resources,
+                targets: &targets,
+            };
+            match card_flow::advance(&mut card, index, &ctx) {
+                NextStep::Perform(action_index) => {
+                    targets = self.perform_action(actor, &card, action_index);
+                    index = action_index + 1;
+                }
+                NextStep::Choose(action_index) => {
+                    let option = match actor {
+                        Actor::Player => {
+                            enemy_ai::choose_option(&mut self.rng, &card, action_index)
+                        }
+                        Actor::Enemy(_) => {
+                            let view = BattleView {
+                                map: &self.map,
+                                positions: &self.positions,
+                                resources: &self.resources,
+                                cards: &self.cards,
+                                enemies: &self.enemies,
+                            };
+                            enemy_ai::policy_for(&self.resources, actor).choose_option(
+                                &mut self.rng,
+                                &view,
+                                actor,
+                                &card,
+                                action_index,
+                            )
+                        }
+                    };
+                    card_flow::choose(&mut card, action_index, option);
+                    targets = vec![];
+                    index = action_index;
+                }
+                NextStep::Done => break,
+            }
+        }
+    }
+
+    fn perform_action(
+        &mut self,
+        actor: &Actor,
+        card: &Card,
+        action_index: usize,
+    ) -> Vec<(usize, usize)> {
+        if let Some(action) = card.actions.get(action_index) {
+            let selection = match scenario_utilities::target_selection(
+                actor,
+                card,
+                action_index,
+                &self.positions,
+                &self.map,
+                &self.resources,
+            ) {
+                Some(selection) if !selection.valid_targets.is_empty() => selection,
+                _ => return vec![],
+            };
+            let targets = match actor {
+                Actor::Player => self.player_targets(&selection, action),
+                Actor::Enemy(_) => {
+                    let view = BattleView {
+                        map: &self.map,
+                        positions: &self.positions,
+                        resources: &self.resources,
+                        cards: &self.cards,
+                        enemies: &self.enemies,
+                    };
+                    enemy_ai::policy_for(&self.resources, actor).select_targets(
+                        &mut self.rng,
+                        &view,
+                        &selection,
+                    )
+                }
+            };
+            resolve_action(
+                actor,
+                action,
+                &targets,
+                &mut self.map,
+                &self.enemies,
+                &self.cards,
+                &mut self.positions,
+                &mut self.resources,
+            );
+            return targets;
+        }
+        vec![]
+    }
+
+    fn player_targets(
+        &mut self,
+        selection: &TargetSelection,
+        action: &CardAction,
+    ) -> Vec<(usize, usize)> {
+        let helpful = match action {
+            CardAction::Heal {
+                amount: _,
+                range: _,
+                shape: _,
+            } => true,
+            CardAction::ApplyStatus {
+                range: _,
+                status,
+                shape: _,
+            } => status.kind.is_beneficial(),
+            _ => false,
+        };
+        match action.target() {
+            Targetable::Path { max_distance: _ } => {
+                let goal = match self.scenario.state {
+                    ScenarioState::InProgress(goal_id) => {
+                        self.map.tiles.iter().find_map(|tile| match tile.tag {
+                            TileTag::Target(i) if i == goal_id => Some(tile.pos),
+                            _ => None,
+                        })
+                    }
+                    _ => None,
+                };
+                if let Some(goal) = goal {
+                    selection
+                        .valid_targets
+                        .iter()
+                        .min_by_key(|p| p.0.abs_diff(goal.0).max(p.1.abs_diff(goal.1)))
+                        .map(|p| vec![*p])
+                        .unwrap_or_default()
+                } else {
+                    enemy_ai::select_target(&mut self.rng, selection)
+                }
+            }
+            Targetable::Creature { max_distance: _ } if helpful => {
+                enemy_ai::position_of(&self.positions, &selection.actor)
+                    .filter(|p| selection.valid_targets.contains(p))
+                    .map(|p| vec![p])
+                    .unwrap_or_default()
+            }
+            Targetable::Creature { max_distance: _ }
+            | Targetable::Attackable { max_distance: _ }
+            | Targetable::Tile { max_distance: _ } => {
+                let enemies = selection
+                    .valid_targets
+                    .iter()
+                    .filter(|p| {
+                        self.positions
+                            .iter()
+                            .any(|(a, pos)| a != &selection.actor && (pos.0, pos.1) == **p)
+                    })
+                    .copied()
+                    .collect::<Vec<_>>();
+                enemies
+                    .into_iter()
+                    .take(selection.num_targets_to_select)
+                    .collect()
+            }
+            Targetable::EmptyTile { max_distance: _ } => {
+                enemy_ai::select_target(&mut self.rng, selection)
+            }
+            Targetable::Caster => selection.valid_targets.clone(),
+        }
+    }
+
+    fn check_outcome(&mut self) -> Option<BattleOutcome> {
+        if let ScenarioState::InProgress(goal_id) = self.scenario.state {
+            if goal_reached(&self.scenario, goal_id, &self.map, &self.positions) {
+                if let ScenarioState::Success(_) = self.scenario.succeed() {
+                    return Some(BattleOutcome::Victory);
+                }
+            }
+        }
+        if player_failed(&self.resources) {
+            self.scenario.fail();
+            return Some(BattleOutcome::Defeat);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_generative_grammars::tracery::TraceryGrammar;
+
+    use super::*;
+    use crate::{
+        run_seed::RngStream,
+        scene::scenario::scenario_map::TileType,
+        story::{Goal, Story},
+    };
+
+    const CARDS: [&str; 2] = [
+        "
+id: strike
+name: Strike
+actions:
+  - !Move 2
+  - !Attack
+    damage: 2
+    range: 1
+tier: 0
+",
+        "
+id: jab
+name: Jab
+actions:
+  - !Attack
+    damage: 1
+    range: 1
+tier: 0
+",
+    ];
+
+    const CORRIDOR: &str = "
+#########
+#S.....1#
+#########
+#...E...#
+#########
+";
+
+    const ARENA: &str = "
+#########
+#S.....1#
+#..E....#
+#....E..#
+#.E.....#
+#########
+";
+
+    fn cards() -> HashMap<String, Card> {
+        CARDS
+            .iter()
+            .map(|yaml| serde_yaml::from_str::<Card>(yaml).unwrap())
+            .map(|card| (card.id.clone(), card))
+            .collect()
+    }
+
+    fn enemy_definitions() -> HashMap<String, EnemyDefinition> {
+        [
+            "id: grunt\nname: Grunt\nhealth: 2\ndeck: [jab]\ntier: 0",
+            "id: brute\nname: Brute\nhealth: 4\ndeck: [strike, jab]\ntier: 0",
+        ]
+        .iter()
+        .map(|yaml| serde_yaml::from_str::<EnemyDefinition>(yaml).unwrap())
+        .map(|enemy| (enemy.id.clone(), enemy))
+        .collect()
+    }
+
+    fn scenario() -> Scenario {
+        Scenario {
+            initial_description: String::new(),
+            state: ScenarioState::InProgress(0),
+            goals: vec![Goal {
+                goal_type: GoalType::ReachLocation("the exit".to_string()),
+                ..Default::default()
+            }],
+            map: Default::default(),
+        }
+    }
+
+    fn actor(health: usize, card: &str, seed: u64) -> ActorResource {
+        ActorResource {
+            health,
+            max_health: health,
+            max_energy: 1,
+            ..Default::default()
+        }
+        .with_deck(vec![card.to_string(); 5], seed)
+    }
+
+    fn battle(grid: &str, enemies: Vec<ActorResource>, seed: u64) -> Battle {
+        let mut resources = enemies
+            .into_iter()
+            .enumerate()
+            .map(|(i, enemy)| (Actor::Enemy(i), enemy))
+            .collect::<HashMap<_, _>>();
+        let turn_order = turn_order(&resources);
+        resources.insert(Actor::Player, actor(20, "strike", seed));
+        Battle::new(
+            scenario(),
+            ScenarioMap::from_grid(grid),
+            ActorResources {
+                resources,
+                turn_order,
+            },
+            cards(),
+            enemy_definitions(),
+            seed,
+        )
+    }
+
+    fn health(battle: &Battle, actor: Actor) -> usize {
+        battle
+            .resources
+            .resources
+            .get(&actor)
+            .map(|res| res.health)
+            .unwrap_or(0)
+    }
+
+    fn act(
+        battle: &mut Battle,
+        actor: Actor,
+        action: &str,
+        target: (usize, usize),
+    ) -> Vec<AnimateActionsEvents> {
+        let action = serde_yaml::from_str::<CardAction>(action).unwrap();
+        resolve_action(
+            &actor,
+            &action,
+            &[target],
+            &mut battle.map,
+            &battle.enemies,
+            &battle.cards,
+            &mut battle.positions,
+            &mut battle.resources,
+        )
+    }
+
+    fn position(battle: &Battle, actor: Actor) -> Option<(usize, usize)> {
+        battle
+            .positions
+            .iter()
+            .find(|(a, _)| *a == actor)
+            .map(|(_, p)| p.into())
+    }
+
+    #[test]
+    fn player_reaches_an_unguarded_goal() {
+        for seed in 0..20 {
+            let mut battle = battle(CORRIDOR, vec![actor(3, "jab", seed)], seed);
+            assert_eq!(battle.run(50), BattleOutcome::Victory, "seed {seed}");
+            assert!(
+                battle.turns_taken <= 6,
+                "seed {seed}: {}",
+                battle.turns_taken
+            );
+            assert_eq!(health(&battle, Actor::Player), 20, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn fixed_seed_replays_the_same_battle() {
+        let enemies = || (0..3).map(|i| actor(3, "strike", i)).collect::<Vec<_>>();
+        for seed in [3, 17, 42] {
+            let mut first = battle(ARENA, enemies(), seed);
+            let mut second = battle(ARENA, enemies(), seed);
+            let outcome = first.run(200);
+            assert_ne!(outcome, BattleOutcome::Timeout, "seed {seed}");
+            assert_eq!(outcome, second.run(200), "seed {seed}");
+            assert_eq!(first.turns_taken, second.turns_taken, "seed {seed}");
+            assert_eq!(first.positions, second.positions, "seed {seed}");
+            assert_eq!(
+                health(&first, Actor::Player),
+                health(&second, Actor::Player),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn turn_passes_on_when_the_active_actor_dies() {
+        let poisoned = ActorResource {
+            statuses: vec![Status::new(StatusKind::Poison, 2)],
+            ..actor(1, "jab", 1)
+        };
+        let enemies = vec![actor(3, "jab", 0), poisoned, actor(3, "jab", 2)];
+        let mut battle = battle(ARENA, enemies, 0);
+
+        assert_eq!(battle.take_turn(Actor::Enemy(1)), None);
+        assert!(!battle.resources.turn_order.contains(&Actor::Enemy(1)));
+        assert_eq!(
+            next_actor(&battle.resources, &Actor::Enemy(1)),
+            Actor::Enemy(2)
+        );
+        assert_eq!(
+            next_actor(&battle.resources, &Actor::Enemy(0)),
+            Actor::Enemy(2)
+        );
+        assert_eq!(
+            next_actor(&battle.resources, &Actor::Enemy(2)),
+            Actor::Player
+        );
+    }
+
+    #[test]
+    fn resolved_impact_replays_to_the_same_state() {
+        let mut battle = battle("#####\n#SE1#\n#####", vec![actor(3, "jab", 0)], 0);
+        let attack = cards().get("strike").unwrap().actions[1].clone();
+        let mut replayed = battle.resources.clone();
+
+        let resolved = resolve_action(
+            &Actor::Player,
+            &attack,
+            &[(2, 1)],
+            &mut battle.map.clone(),
+            &battle.enemies,
+            &battle.cards,
+            &mut battle.positions.clone(),
+            &mut battle.resources,
+        );
+        for event in resolved.iter() {
+            if let AnimateActionsEvents::Attack(_, impact) = event {
+                for adjustment in impact.actors.iter() {
+                    apply_adjustment(&mut replayed, adjustment);
+                }
+            }
+        }
+
+        assert_eq!(health(&battle, Actor::Enemy(0)), 1);
+        assert_eq!(
+            replayed
+                .resources
+                .get(&Actor::Enemy(0))
+                .map(|res| res.health),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn attacks_carry_tile_damage_in_their_impact() {
+        let mut battle = battle("#####\n#So1#\n#####", vec![], 0);
+        let attack = cards().get("strike").unwrap().actions[1].clone();
+
+        let resolved = resolve_action(
+            &Actor::Player,
+            &attack,
+            &[(2, 1)],
+            &mut battle.map,
+            &battle.enemies,
+            &battle.cards,
+            &mut battle.positions,
+            &mut battle.resources,
+        );
+
+        let tiles = resolved
+            .iter()
+            .flat_map(|event| match event {
+                AnimateActionsEvents::Attack(_, impact) => impact.tiles.clone(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+        assert!(matches!(tiles[..], [AdjustTileEvent::Damage((2, 1), 2)]));
+        assert_eq!(
+            battle.map.tile((2, 1)).map(|tile| tile.tile_type),
+            Some(TileType::Floor)
+        );
+    }
+
+    #[test]
+    fn terrain_acts_at_the_end_of_a_turn() {
+        let idle = |health: usize| ActorResource {
+            draw_pile: vec![],
+            max_health: 3,
+            ..actor(health, "jab", 0)
+        };
+        let mut battle = battle(
+            "
+#######
+#S...1#
+#.E^E+#
+#######
+",
+            vec![idle(1), idle(1)],
+            0,
+        );
+        for (actor, position) in battle.positions.iter_mut() {
+            match actor {
+                Actor::Enemy(0) => *position = ActorPosition(3, 2),
+                Actor::Enemy(1) => *position = ActorPosition(5, 2),
+                _ => {}
+            }
+        }
+
+        battle.take_turn(Actor::Enemy(0));
+        assert_eq!(health(&battle, Actor::Enemy(0)), 0);
+        assert!(!battle.resources.turn_order.contains(&Actor::Enemy(0)));
+        assert!(battle.positions.iter().all(|(a, _)| *a != Actor::Enemy(0)));
+
+        battle.take_turn(Actor::Enemy(1));
+        assert_eq!(health(&battle, Actor::Enemy(1)), 2);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct RunSample {
+        story: Vec<String>,
+        maps: Vec<Vec<((usize, usize), TileType, TileTag)>>,
+        hands: Vec<Vec<(Actor, Option<String>, Vec<String>)>>,
+        choices: Vec<(Option<String>, Vec<(usize, usize)>)>,
+    }
+
+    fn sample(
+        story: &mut RngComponent,
+        map: &mut RngComponent,
+        enemy_hands: &mut RngComponent,
+        ai: &mut RngComponent,
+    ) -> RunSample {
+        let grammar = serde_yaml::from_str::<TraceryGrammar>(include_str!(
+            "../../../assets/test_story.trace.yaml"
+        ))
+        .unwrap();
+        let mut run = Story::generate(story, &grammar);
+        let scenarios = (0..3)
+            .filter_map(|_| run.generate_next_scenario(None))
+            .collect::<Vec<_>>();
+        let mut sample = RunSample {
+            story: scenarios
+                .iter()
+                .map(|scenario| scenario.initial_description.clone())
+                .collect(),
+            maps: vec![],
+            hands: vec![],
+            choices: vec![],
+        };
+        let card = cards().get("strike").cloned().unwrap();
+        for scenario in scenarios.iter() {
+            let enemies = generate_enemies(enemy_hands, &enemy_definitions(), &cards(), 0);
+            let generated = ScenarioMap::generate(map, scenario, &enemies, &HashMap::default());
+            sample.maps.push(
+                generated
+                    .tiles
+                    .iter()
+                    .map(|tile| (tile.pos, tile.tile_type, tile.tag))
+                    .collect(),
+            );
+            let mut hands = enemies
+                .into_iter()
+                .map(|(actor, res)| (actor, res.enemy, res.draw_pile))
+                .collect::<Vec<_>>();
+            hands.sort_by_key(|(actor, _, _)| *actor);
+            for (actor, _, hand) in hands.iter() {
+                let selection = TargetSelection {
+                    actor: *actor,
+                    card: card.clone(),
+                    valid_targets: start_positions(&generated)
+                        .into_iter()
+                        .map(|(_, p)| (p.0, p.1))
+                        .collect(),
+                    num_targets_to_select: 1,
+                    action_id: 0,
+                };
+                sample.choices.push((
+                    enemy_ai::choose_card(ai, hand),
+                    enemy_ai::select_target(ai, &selection),
+                ));
+            }
+            sample.hands.push(hands);
+        }
+        sample
+    }
+
+    #[test]
+    fn one_seed_replays_story_maps_hands_and_ai() {
+        let seed = RunSeed(1234);
+        let mut rngs = RunRngs::new(seed);
+        let first = sample(
+            &mut rngs.story,
+            &mut rngs.map,
+            &mut rngs.enemy_hands,
+            &mut rngs.enemy_ai,
+        );
+        // Each stream only depends on the seed, so streams made on their own replay the run
+        let second = sample(
+            &mut seed.stream(RngStream::Story),
+            &mut seed.stream(RngStream::Map),
+            &mut seed.stream(RngStream::EnemyHands),
+            &mut seed.stream(RngStream::EnemyAi),
+        );
+        assert!(!first.story.is_empty());
+        assert_eq!(first, second);
+
+        let mut other = RunRngs::new(RunSeed(4321));
+        let different = sample(
+            &mut other.story,
+            &mut other.map,
+            &mut other.enemy_hands,
+            &mut other.enemy_ai,
+        );
+        assert_ne!(first.maps, different.maps);
+    }
+
+    const SKIRMISH: &str = "
+#######
+#SE...#
+#..E..#
+#.....#
+#######
+";
+
+    #[test]
+    fn heal_restores_health_up_to_the_maximum() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+        battle
+            .resources
+            .resources
+            .get_mut(&Actor::Enemy(0))
+            .unwrap()
+            .health = 1;
+
+        let resolved = act(
+            &mut battle,
+            Actor::Player,
+            "!Heal\namount: 5\nrange: 1",
+            (2, 1),
+        );
+
+        assert_eq!(health(&battle, Actor::Enemy(0)), 3);
+        assert!(matches!(
+            resolved.as_slice(),
+            [AnimateActionsEvents::Heal(Actor::Player, impact)]
+                if matches!(impact.actors.as_slice(), [AdjustActorEvent::Heal(Actor::Enemy(0), 5)])
+        ));
+    }
+
+    #[test]
+    fn push_and_pull_stop_at_walls() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+
+        let resolved = act(
+            &mut battle,
+            Actor::Player,
+            "!Push\ndistance: 2\nrange: 1",
+            (2, 1),
+        );
+        assert_eq!(position(&battle, Actor::Enemy(0)), Some((4, 1)));
+        assert!(matches!(
+            resolved.as_slice(),
+            [AnimateActionsEvents::Push(Actor::Enemy(0), path)]
+                if path == &vec![ActorPosition(3, 1), ActorPosition(4, 1)]
+        ));
+
+        act(
+            &mut battle,
+            Actor::Player,
+            "!Push\ndistance: 5\nrange: 3",
+            (4, 1),
+        );
+        assert_eq!(position(&battle, Actor::Enemy(0)), Some((5, 1)));
+
+        act(
+            &mut battle,
+            Actor::Player,
+            "!Pull\ndistance: 2\nrange: 4",
+            (5, 1),
+        );
+        assert_eq!(position(&battle, Actor::Enemy(0)), Some((3, 1)));
+
+        // Nothing stands on an empty tile, so there is nothing to move
+        let resolved = act(
+            &mut battle,
+            Actor::Player,
+            "!Pull\ndistance: 2\nrange: 4",
+            (5, 3),
+        );
+        assert!(resolved.is_empty());
+    }
+
+    #[test]
+    fn teleport_moves_the_caster_straight_to_the_target() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+
+        let resolved = act(&mut battle, Actor::Player, "!Teleport\nrange: 3", (4, 3));
+
+        assert_eq!(position(&battle, Actor::Player), Some((4, 3)));
+        assert!(matches!(
+            resolved.as_slice(),
+            [AnimateActionsEvents::Teleport(
+                Actor::Player,
+                ActorPosition(4, 3)
+            )]
+        ));
+    }
+
+    #[test]
+    fn area_attacks_hit_everything_along_the_line() {
+        let mut battle = battle(
+            "#######\n#SEoE.#\n#######",
+            vec![actor(3, "jab", 0), actor(3, "jab", 1)],
+            0,
+        );
+
+        let resolved = act(
+            &mut battle,
+            Actor::Player,
+            "!Attack\ndamage: 1\nrange: 1\nshape: !Line\n  length: 4",
+            (2, 1),
+        );
+
+        assert_eq!(health(&battle, Actor::Enemy(0)), 2);
+        assert_eq!(health(&battle, Actor::Enemy(1)), 2);
+        assert_eq!(health(&battle, Actor::Player), 20);
+        match resolved.as_slice() {
+            [AnimateActionsEvents::AreaAttack(Actor::Player, impact)] => {
+                assert_eq!(impact.actors.len(), 2);
+                assert!(matches!(
+                    impact.tiles.as_slice(),
+                    [AdjustTileEvent::Damage((3, 1), 1)]
+                ));
+            }
+            other => panic!("unexpected events {other:?}"),
+        }
+    }
+
+    #[test]
+    fn draw_reshuffles_the_discard_pile_when_the_draw_pile_runs_out() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+        let player = battle.resources.resources.get_mut(&Actor::Player).unwrap();
+        player.draw_pile = vec!["strike".to_string()];
+        player.discarded = vec!["jab".to_string(), "jab".to_string()];
+
+        act(&mut battle, Actor::Player, "!Draw 2", (1, 1));
+
+        let player = &battle.resources.resources[&Actor::Player];
+        assert_eq!(player.hand, vec!["strike".to_string(), "jab".to_string()]);
+        assert_eq!(player.draw_pile, vec!["jab".to_string()]);
+        assert!(player.discarded.is_empty());
+    }
+
+    #[test]
+    fn block_absorbs_the_next_damage() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+
+        act(&mut battle, Actor::Player, "!Block 2", (1, 1));
+        apply_adjustment(
+            &mut battle.resources,
+            &AdjustActorEvent::Damage(Actor::Player, 3),
+        );
+
+        assert_eq!(health(&battle, Actor::Player), 19);
+        assert!(battle.resources.resources[&Actor::Player]
+            .statuses
+            .is_empty());
+    }
+
+    #[test]
+    fn summoned_enemies_join_the_battle_with_known_cards() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+        battle.enemies.insert(
+            "grunt".to_string(),
+            serde_yaml::from_str("id: grunt\nname: Grunt\nhealth: 2\ndeck: [jab, lost]\ntier: 0")
+                .unwrap(),
+        );
+
+        let resolved = act(
+            &mut battle,
+            Actor::Enemy(1),
+            "!Summon\nenemy: grunt\nrange: 2",
+            (4, 3),
+        );
+
+        assert!(matches!(
+            resolved.as_slice(),
+            [AnimateActionsEvents::Summon(Actor::Enemy(1), _)]
+        ));
+        assert_eq!(position(&battle, Actor::Enemy(2)), Some((4, 3)));
+        assert!(battle.resources.turn_order.contains(&Actor::Enemy(2)));
+        let summoned = &battle.resources.resources[&Actor::Enemy(2)];
+        assert_eq!(summoned.health, 2);
+        assert_eq!(summoned.draw_pile, vec!["jab".to_string()]);
+
+        // Only enemies call for help
+        let resolved = act(
+            &mut battle,
+            Actor::Player,
+            "!Summon\nenemy: grunt\nrange: 2",
+            (5, 3),
+        );
+        assert!(resolved.is_empty());
+        assert_eq!(position(&battle, Actor::Enemy(3)), None);
+    }
+
+    #[test]
+    fn attack_bonuses_add_to_the_resolved_damage() {
+        let mut battle = battle(SKIRMISH, vec![actor(9, "jab", 0), actor(9, "jab", 1)], 0);
+        battle
+            .resources
+            .resources
+            .get_mut(&Actor::Enemy(0))
+            .unwrap()
+            .add_status(Status::new(StatusKind::Stun, 1));
+        let exploit =
+            "!Attack\ndamage: 1\nrange: 2\nbonus:\n  - when: !TargetHas Stun\n    damage: 2";
+
+        act(&mut battle, Actor::Player, exploit, (2, 1));
+        act(&mut battle, Actor::Player, exploit, (3, 2));
+
+        assert_eq!(health(&battle, Actor::Enemy(0)), 6);
+        assert_eq!(health(&battle, Actor::Enemy(1)), 8);
+    }
+
+    #[test]
+    fn cards_costing_more_than_the_energy_left_are_rejected() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+        let card = battle.cards["strike"].clone();
+        start_turn(&mut battle.resources, &Actor::Player);
+        let player = battle.resources.resources.get_mut(&Actor::Player).unwrap();
+        player.energy = card.cost - 1;
+
+        assert!(!can_play(&battle.resources, &Actor::Player, &card));
+        assert!(!play_card(&mut battle.resources, &Actor::Player, &card));
+        let player = &battle.resources.resources[&Actor::Player];
+        assert_eq!(player.hand, vec!["strike".to_string(); 5]);
+        assert_eq!(player.cards_played, 0);
+
+        battle
+            .resources
+            .resources
+            .get_mut(&Actor::Player)
+            .unwrap()
+            .energy = card.cost;
+        assert!(play_card(&mut battle.resources, &Actor::Player, &card));
+        assert_eq!(battle.resources.resources[&Actor::Player].energy, 0);
+    }
+
+    #[test]
+    fn energy_and_plays_reset_at_turn_start() {
+        let mut battle = battle(SKIRMISH, vec![actor(3, "jab", 0), actor(3, "jab", 1)], 0);
+        let player = battle.resources.resources.get_mut(&Actor::Player).unwrap();
+        player.max_energy = 3;
+        player.energy = 0;
+        player.cards_played = MAX_CARDS_PER_TURN;
+
+        assert!(playable_cards(&battle.resources, &Actor::Player, &battle.cards).is_empty());
+        start_turn(&mut battle.resources, &Actor::Player);
+
+        let player = &battle.resources.resources[&Actor::Player];
+        assert_eq!(player.energy, 3);
+        assert_eq!(player.cards_played, 0);
+        assert_eq!(
+            playable_cards(&battle.resources, &Actor::Player, &battle.cards),
+            vec!["strike".to_string(); 5]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+use super::types::{Actor, ActorResource};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StatusKind {
+    Stun,
+    Poison,
+    Burn,
+    Shield,
+    Haste,
+    Slow,
+    Root,
+    Weakness,
+    Vulnerability,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackRule {
+    Refresh,
+    Intensify,
+    Extend,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickPhase {
+    TurnStart,
+    TurnEnd,
+}
+
+impl StatusKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Stun => "Stun",
+            StatusKind::Poison => "Poison",
+            StatusKind::Burn => "Burn",
+            StatusKind::Shield => "Shield",
+            StatusKind::Haste => "Haste",
+            StatusKind::Slow => "Slow",
+            StatusKind::Root => "Root",
+            StatusKind::Weakness => "Weakness",
+            StatusKind::Vulnerability => "Vulnerability",
+        }
+    }
+
+    pub fn stack_rule(&self) -> StackRule {
+        match self {
+            StatusKind::Poison | StatusKind::Shield => StackRule::Intensify,
+            StatusKind::Burn => StackRule::Extend,
+            _ => StackRule::Refresh,
+        }
+    }
+
+    pub fn tick_phase(&self) -> TickPhase {
+        match self {
+            StatusKind::Stun | StatusKind::Poison | StatusKind::Shield => TickPhase::TurnStart,
+            _ => TickPhase::TurnEnd,
+        }
+    }
+
+    pub fn is_beneficial(&self) -> bool {
+        matches!(self, StatusKind::Shield | StatusKind::Haste)
+    }
+}
+
+fn one() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    pub kind: StatusKind,
+    pub duration: usize,
+    #[serde(default = "one")]
+    pub stacks: usize,
+}
+
+impl Status {
+    pub fn new(kind: StatusKind, duration: usize) -> Self {
+        Self {
+            kind,
+            duration,
+            stacks: 1,
+        }
+    }
+
+    pub fn block(amount: usize) -> Self {
+        Self {
+            kind: StatusKind::Shield,
+            duration: 1,
+            stacks: amount,
+        }
+    }
+
+    pub fn affects(&self, caster: &Actor, target: &Actor) -> bool {
+        caster != target || self.kind.is_beneficial()
+    }
+
+    pub fn describe(&self) -> String {
+        let turns = if self.duration == 1 { "turn" } else { "turns" };
+        if self.stacks > 1 {
+            format!(
+                "{} x{} ({} {turns})",
+                self.kind.name(),
+                self.stacks,
+                self.duration
+            )
+        } else {
+            format!("{} ({} {turns})", self.kind.name(), self.duration)
+        }
+    }
+}
+
+impl ActorResource {
+    pub fn status(&self, kind: StatusKind) -> Option<&Status> {
+        self.statuses.iter().find(|status| status.kind == kind)
+    }
+
+    pub fn has_status(&self, kind: StatusKind) -> bool {
+        self.status(kind).is_some()
+    }
+
+    fn stacks(&self, kind: StatusKind) -> usize {
+        self.status(kind).map(|status| status.stacks).unwrap_or(0)
+    }
+
+    pub fn add_status(&mut self, status: Status) {
+        if status.duration == 0 || status.stacks == 0 {
+            return;
+        }
+        match self.statuses.iter_mut().find(|s| s.kind == status.kind) {
+            Some(existing) => match status.kind.stack_rule() {
+                StackRule::Refresh => {
+                    existing.duration = existing.duration.max(status.duration);
+                    existing.stacks = existing.stacks.max(status.stacks);
+                }
+                StackRule::Intensify => {
+                    existing.duration = existing.duration.max(status.duration);
+                    existing.stacks += status.stacks;
+                }
+                StackRule::Extend => {
+                    existing.duration += status.duration;
+                    existing.stacks = existing.stacks.max(status.stacks);
+                }
+            },
+            None => {
+                self.statuses.push(status);
+                self.statuses.sort_by_key(|s| s.kind);
+            }
+        }
+    }
+
+    pub fn tick_statuses(&mut self, phase: TickPhase) -> usize {
+        let mut damage = 0;
+        for status in self.statuses.iter_mut() {
+            if status.kind.tick_phase() != phase {
+                continue;
+            }
+            if let StatusKind::Poison | StatusKind::Burn = status.kind {
+                damage += status.stacks;
+            }
+            status.duration = status.duration.saturating_sub(1);
+        }
+        self.statuses.retain(|status| status.duration > 0);
+        damage
+    }
+
+    pub fn status_pressure(&self) -> f32 {
+        self.statuses
+            .iter()
+            .map(|status| {
+                let weight = (status.duration * status.stacks) as f32;
+                if status.kind.is_beneficial() {
+                    -weight
+                } else {
+                    weight
+                }
+            })
+            .sum()
+    }
+
+    pub fn movement_range(&self, distance: usize) -> usize {
+        if self.has_status(StatusKind::Root) {
+            return 0;
+        }
+        let hasted = distance + self.stacks(StatusKind::Haste);
+        hasted
+            .saturating_sub(self.stacks(StatusKind::Slow))
+            .max(distance.min(1))
+    }
+
+    pub fn outgoing_damage(&self, damage: usize) -> usize {
+        damage
+            .saturating_sub(self.stacks(StatusKind::Weakness))
+            .max(damage.min(1))
+    }
+
+    pub fn incoming_damage(&mut self, damage: usize) -> usize {
+        if damage == 0 {
+            return 0;
+        }
+        let mut damage = damage + self.stacks(StatusKind::Vulnerability);
+        if let Some(shield) = self
+            .statuses
+            .iter_mut()
+            .find(|status| status.kind == StatusKind::Shield)
+        {
+            let absorbed = shield.stacks.min(damage);
+            shield.stacks -= absorbed;
+            damage -= absorbed;
+        }
+        self.statuses.retain(|status| status.stacks > 0);
+        damage
+    }
+}
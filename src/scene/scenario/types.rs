@@ -1,33 +1,61 @@
 use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
 
-use crate::card::Card;
+use super::status::Status;
+use crate::{card::Card, enemy::AiProfile};
 
 #[derive(Debug, Clone)]
 pub struct TargetSelection {
     pub actor: Actor,
     pub card: Card,
     pub valid_targets: Vec<(usize, usize)>,
+    pub affected_tiles: HashMap<(usize, usize), Vec<(usize, usize)>>,
     pub num_targets_to_select: usize,
     pub action_id: usize,
 }
 
+impl TargetSelection {
+    pub fn affected_by(&self, targets: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let mut tiles = targets
+            .iter()
+            .flat_map(|target| {
+                self.affected_tiles
+                    .get(target)
+                    .cloned()
+                    .unwrap_or_else(|| vec![*target])
+            })
+            .collect::<Vec<_>>();
+        tiles.sort();
+        tiles.dedup();
+        tiles
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AnimateActionsEvents {
     Wait(f32),
     Continue(Actor),
     SelectTargets(TargetSelection),
-    Move(Actor, ActorPosition),
-    Attack(Actor, ActorPosition, usize),
-    Stun(Actor, ActorPosition, usize),
+    Move(Actor, Vec<ActorPosition>),
+    Attack(Actor, Impact),
+    ApplyStatus(Actor, Impact),
+    Heal(Actor, Impact),
+    Push(Actor, Vec<ActorPosition>),
+    Teleport(Actor, ActorPosition),
+    AreaAttack(Actor, Impact),
+    Draw(Actor, Impact),
+    Summon(Actor, Impact),
     SetTurnProcess(CurrentTurnProcess),
 }
 
-#[derive(Debug, Clone, Resource)]
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
 pub enum CurrentTurnProcess {
     None,
     Thinking(Actor),
     CardActionTriggered(Actor, Card, usize),
+    CardChoice(Actor, Card, usize),
     CardTargetsSelected(Actor, Card, Vec<(usize, usize)>, usize),
+    CardResolved(Actor),
     Done(Actor),
 }
 
@@ -37,7 +65,10 @@ pub struct CardPlayedEvent {
     pub card: String,
 }
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
+pub struct CardChoiceEvent(pub usize);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Actor {
     Player,
     Enemy(usize),
@@ -60,7 +91,7 @@ impl Ord for Actor {
     }
 }
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ActorPosition(pub usize, pub usize);
 
 impl From<ActorPosition> for (usize, usize) {
@@ -74,14 +105,34 @@ impl From<&ActorPosition> for (usize, usize) {
     }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct ActorResource {
+    #[serde(default)]
+    pub draw_pile: Vec<String>,
     pub hand: Vec<String>,
-    pub used: Vec<String>,
     pub discarded: Vec<String>,
+    #[serde(default)]
+    pub exhausted: Vec<String>,
+    #[serde(default)]
+    pub shuffle_seed: u64,
     pub health: usize,
     pub max_health: usize,
-    pub stun_duration: usize,
+    #[serde(default)]
+    pub energy: usize,
+    #[serde(default = "default_max_energy")]
+    pub max_energy: usize,
+    #[serde(default)]
+    pub cards_played: usize,
+    #[serde(default)]
+    pub statuses: Vec<Status>,
+    #[serde(default)]
+    pub enemy: Option<String>,
+    #[serde(default)]
+    pub ai: AiProfile,
+}
+
+fn default_max_energy() -> usize {
+    1
 }
 
 #[derive(Default, Debug, Clone, Resource)]
@@ -90,13 +141,13 @@ pub struct ActorResources {
     pub turn_order: Vec<Actor>,
 }
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Goal {
     pub number: usize,
     pub status: GoalStatus,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GoalStatus {
     Hidden,
     Active,
@@ -106,5 +157,20 @@ pub enum GoalStatus {
 #[derive(Debug, Clone)]
 pub enum AdjustActorEvent {
     Damage(Actor, usize),
-    Stun(Actor, usize),
+    Heal(Actor, usize),
+    ApplyStatus(Actor, Status),
+    Draw(Actor, usize),
+    Summon(Actor, ActorPosition, ActorResource),
+}
+
+/// The adjustments an action resolved to, sent once its animation lands
+#[derive(Default, Debug, Clone)]
+pub struct Impact {
+    pub actors: Vec<AdjustActorEvent>,
+    pub tiles: Vec<AdjustTileEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub enum AdjustTileEvent {
+    Damage((usize, usize), usize),
 }
@@ -1,8 +1,8 @@
 use crate::assets;
 use crate::card::AvailableCards;
-use crate::card::CardSelectedEvent;
 use crate::card::CardUI;
 use crate::card::Cards;
+use crate::card::SelectedCounter;
 use crate::game_state::AppState;
 use crate::ui::*;
 use bevy::prelude::*;
@@ -20,18 +20,11 @@ impl Plugin for FailPhasePlugin {
         app.add_system_set(
             SystemSet::on_enter(SceneState::Failed).with_system(display_failure_men),
         )
-        .add_system_set(
-            SystemSet::on_update(SceneState::Failed)
-                .with_system(card_select)
-                .with_system(click_event),
-        )
+        .add_system_set(SystemSet::on_update(SceneState::Failed).with_system(click_event))
         .add_system_set(clear_ui_system_set(SceneState::Failed));
     }
 }
 
-#[derive(Component)]
-struct SelectedCounter(usize, usize);
-
 pub(crate) fn display_failure_men(
     mut commands: Commands,
     assets: Res<assets::Assets>,
@@ -47,27 +40,7 @@ pub(crate) fn display_failure_men(
             MainText::new(goal).spawn(parent, &assets);
         }
 
-        let available_cards = {
-            let current_cards = available_cards.cards.keys().collect::<Vec<_>>();
-            let remaining_cards = cards
-                .cards
-                .iter()
-                .filter(|(key, _)| !current_cards.contains(key))
-                .collect::<Vec<_>>();
-            let min = remaining_cards
-                .iter()
-                .fold(usize::MAX, |v, (_, card)| v.min(card.tier));
-            remaining_cards
-                .iter()
-                .filter_map(|(_, card)| {
-                    if card.tier > min {
-                        None
-                    } else {
-                        Some((**card).clone())
-                    }
-                })
-                .collect::<Vec<_>>()
-        };
+        let available_cards = cards.next_unlocks(&available_cards);
 
         if available_cards.is_empty() {
             MainText::new("You have nothing to learn, and everything to prove.");
@@ -79,67 +52,17 @@ pub(crate) fn display_failure_men(
             "And yet - we all grow through our failures.\nChoose a card to add to your deck:",
         );
 
-        parent
-            .spawn(NodeBundle {
-                style: Style {
-                    flex_direction: FlexDirection::Row,
-                    justify_content: JustifyContent::Center,
-                    flex_wrap: FlexWrap::Wrap,
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .with_children(|parent| {
-                for card in available_cards.iter() {
-                    CardUI::card(card).selectable().spawn(parent, &assets);
-                }
-            });
-        parent.spawn((NodeBundle::default(), SelectedCounter(0, 1)));
+        CardUI::spawn_row(
+            parent,
+            &assets,
+            available_cards
+                .iter()
+                .map(|card| CardUI::card(card).selectable()),
+        );
+        SelectedCounter::new(1, "continue-story-cards", "Continue Story").spawn(parent);
     });
 }
 
-fn card_select(
-    mut events: EventReader<CardSelectedEvent>,
-    mut commands: Commands,
-    mut counters: Query<(Entity, &mut SelectedCounter)>,
-    assets: Res<assets::Assets>,
-) {
-    let mut added = 0;
-    let mut removed = 0;
-
-    for event in events.iter() {
-        if event.0 {
-            added += 1;
-        } else {
-            removed += 1;
-        }
-    }
-
-    for (entity, mut counter) in counters.iter_mut() {
-        counter.0 += added;
-        counter.0 = counter.0.checked_sub(removed).unwrap_or_default();
-
-        let selected = counter.0;
-        let total = counter.1;
-
-        commands.entity(entity).despawn_descendants();
-        commands.entity(entity).add_children(|parent| {
-            MainText::new(format!("Selected {selected}/{total} Cards"))
-                .size(10.)
-                .spawn(parent, &assets);
-
-            if selected == total {
-                MenuButton::Primary.spawn(
-                    "continue-story-cards",
-                    "Continue Story",
-                    parent,
-                    &assets,
-                );
-            }
-        });
-    }
-}
-
 fn click_event(
     mut events: EventReader<ButtonClickEvent>,
     cards: Query<&CardUI>,
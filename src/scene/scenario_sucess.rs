@@ -1,4 +1,8 @@
 use crate::assets;
+use crate::card::AvailableCards;
+use crate::card::CardUI;
+use crate::card::Cards;
+use crate::card::SelectedCounter;
 use crate::game_state::AppState;
 use crate::ui::*;
 use bevy::prelude::*;
@@ -25,13 +29,37 @@ pub(crate) fn display_success_menu(
     mut commands: Commands,
     assets: Res<assets::Assets>,
     scenario: Res<Scenario>,
+    cards: Res<Cards>,
+    available_cards: Res<AvailableCards>,
 ) {
     UiRoot::spawn(&mut commands, |parent| {
         MainText::new("Success!").size(100.).spawn(parent, &assets);
         if let ScenarioState::Success(goal) = &scenario.state {
             MainText::new(goal).spawn(parent, &assets);
         }
-        MenuButton::Primary.spawn("continue-to-overworld", "Continue", parent, &assets);
+
+        let unlocks = cards.next_unlocks(&available_cards);
+        let upgrades = cards.upgrade_options(available_cards.cards.keys());
+
+        if unlocks.is_empty() && upgrades.is_empty() {
+            MenuButton::Primary.spawn("continue-to-overworld", "Continue", parent, &assets);
+            return;
+        }
+
+        MainText::new("Choose a new card or an upgrade:").spawn(parent, &assets);
+        CardUI::spawn_row(
+            parent,
+            &assets,
+            unlocks
+                .iter()
+                .map(|card| CardUI::card(card).selectable())
+                .chain(
+                    upgrades
+                        .iter()
+                        .map(|(base, upgraded)| CardUI::upgrade(base, upgraded).selectable()),
+                ),
+        );
+        SelectedCounter::new(1, "continue-to-overworld-cards", "Continue").spawn(parent);
     });
 }
 
@@ -39,10 +67,26 @@ pub(crate) fn check_click(
     mut app_state: ResMut<State<AppState>>,
     mut scene_state: ResMut<State<SceneState>>,
     mut clicked: EventReader<ButtonClickEvent>,
+    cards: Query<&CardUI>,
+    card_collection: Res<Cards>,
+    mut available_cards: ResMut<AvailableCards>,
 ) {
     for click in clicked.iter() {
         let ButtonClickEvent(val, _) = click;
-        if val == "continue-to-overworld" {
+        if val == "continue-to-overworld-cards" {
+            for card in cards.iter().filter(|card| card.selected) {
+                if let Some(base) = &card.upgrades {
+                    available_cards.cards.remove(base);
+                }
+                if let Some(selected) = card_collection.cards.get(&card.card_id) {
+                    available_cards
+                        .cards
+                        .insert(selected.id.clone(), selected.clone());
+                }
+            }
+            let _ = scene_state.set(SceneState::None);
+            let _ = app_state.set(AppState::Overworld);
+        } else if val == "continue-to-overworld" {
             let _ = scene_state.set(SceneState::None);
             let _ = app_state.set(AppState::Overworld);
         }
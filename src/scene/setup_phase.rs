@@ -1,11 +1,13 @@
 use crate::{
     assets,
     card::*,
+    run_seed::RunRngs,
     scene::{scenario::*, SceneState},
     story::*,
     ui::*,
 };
 use bevy::prelude::*;
+use bevy_turborand::DelegatedRng;
 
 pub struct SetupPhasePlugin;
 
@@ -14,18 +16,11 @@ impl Plugin for SetupPhasePlugin {
         app.add_system_set(
             SystemSet::on_enter(SceneState::Setup).with_system(display_setup_phase_menu),
         )
-        .add_system_set(
-            SystemSet::on_update(SceneState::Setup)
-                .with_system(card_select)
-                .with_system(click_event),
-        )
+        .add_system_set(SystemSet::on_update(SceneState::Setup).with_system(click_event))
         .add_system_set(clear_ui_system_set(SceneState::Setup));
     }
 }
 
-#[derive(Component)]
-struct SelectedCounter(usize, usize);
-
 fn display_setup_phase_menu(
     mut commands: Commands,
     scenario: Res<Scenario>,
@@ -63,69 +58,27 @@ fn display_setup_phase_menu(
                             .spawn(parent, &assets);
                     }
                     MainText::new("Choose your cards:").spawn(parent, &assets);
-                    parent
-                        .spawn(NodeBundle {
-                            style: Style {
-                                flex_direction: FlexDirection::Row,
-                                justify_content: JustifyContent::Center,
-                                flex_wrap: FlexWrap::Wrap,
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        })
-                        .with_children(|parent| {
-                            for (_, card) in cards.cards.iter() {
-                                CardUI::card(card).selectable().spawn(parent, &assets);
-                            }
-                        });
-                    parent.spawn((NodeBundle::default(), SelectedCounter(0, 3)));
+                    CardUI::spawn_row(
+                        parent,
+                        &assets,
+                        cards
+                            .cards
+                            .values()
+                            .map(|card| CardUI::card(card).selectable()),
+                    );
+                    SelectedCounter::new(3, "setup-complete", "Complete Setup").spawn(parent);
                 }
             });
     });
 }
 
-fn card_select(
-    mut events: EventReader<CardSelectedEvent>,
-    mut commands: Commands,
-    mut counters: Query<(Entity, &mut SelectedCounter)>,
-    assets: Res<assets::Assets>,
-) {
-    let mut added = 0;
-    let mut removed = 0;
-
-    for event in events.iter() {
-        if event.0 {
-            added += 1;
-        } else {
-            removed += 1;
-        }
-    }
-
-    for (entity, mut counter) in counters.iter_mut() {
-        counter.0 += added;
-        counter.0 = counter.0.checked_sub(removed).unwrap_or_default();
-
-        let selected = counter.0;
-        let total = counter.1;
-
-        commands.entity(entity).despawn_descendants();
-        commands.entity(entity).add_children(|parent| {
-            MainText::new(format!("Selected {selected}/{total} Cards"))
-                .size(10.)
-                .spawn(parent, &assets);
-
-            if selected == total {
-                MenuButton::Primary.spawn("setup-complete", "Complete Setup", parent, &assets);
-            }
-        });
-    }
-}
-
 fn click_event(
     mut events: EventReader<ButtonClickEvent>,
     cards: Query<&CardUI>,
     mut scene_state: ResMut<State<SceneState>>,
     mut actor_resources: ResMut<ActorResources>,
+    mut commands: Commands,
+    mut rngs: ResMut<RunRngs>,
 ) {
     for event in events.iter() {
         if event.0 == "setup-complete" {
@@ -143,12 +96,14 @@ fn click_event(
             actor_resources.resources.insert(
                 Actor::Player,
                 ActorResource {
-                    hand: selected,
                     health: 5,
                     max_health: 5,
+                    max_energy: 3,
                     ..Default::default()
-                },
+                }
+                .with_deck(selected, rngs.deck.u64(..)),
             );
+            commands.insert_resource(CurrentTurnProcess::Thinking(Actor::Player));
             let _ = scene_state.set(SceneState::PlayerTurn);
         }
     }
@@ -1,19 +1,26 @@
-use bevy::prelude::Resource;
+use bevy::{prelude::Resource, utils::HashMap};
 use bevy_generative_grammars::{
     generator::StatefulGenerator,
     tracery::{StatefulStringGenerator, TraceryGrammar},
 };
 use bevy_turborand::{DelegatedRng, RngComponent};
+use serde::{Deserialize, Serialize};
+
+use crate::scene::scenario::scenario_map::generators::{MapLayout, MapStyle};
+
+// Scenarios it takes a new story to reach its Complete phase
+const STORY_LENGTH: usize = 3;
 
 #[derive(Debug, Clone, Resource)]
 pub struct Story {
     pub phase: StoryPhase,
     pub scenarios: Vec<Scenario>,
+    pub generated_scenarios: usize,
     rng: RngComponent,
     generator: StatefulStringGenerator,
 }
 
-#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum StoryPhase {
     Setup,
     Start,
@@ -31,31 +38,70 @@ impl Default for StoryPhase {
     }
 }
 
-impl StoryPhase {}
+impl StoryPhase {
+    pub fn enemy_tier(&self) -> usize {
+        match self {
+            StoryPhase::Setup | StoryPhase::Start => 0,
+            StoryPhase::FinalConfrontation | StoryPhase::Complete => 1,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            StoryPhase::Setup => "intro",
+            StoryPhase::Start => "intro",
+            StoryPhase::FinalConfrontation => "confrontation",
+            StoryPhase::Complete => "complete",
+        }
+    }
 
-#[derive(Clone, Debug, Resource)]
+    pub fn map_style(&self, layout: Option<MapLayout>, authored: Option<String>) -> MapStyle {
+        match self {
+            StoryPhase::Setup | StoryPhase::Start => MapStyle {
+                layout: layout.unwrap_or(MapLayout::Scatter),
+                min_size: 10,
+                max_size: 16,
+                authored,
+            },
+            StoryPhase::FinalConfrontation | StoryPhase::Complete => MapStyle {
+                layout: layout.unwrap_or(MapLayout::Rooms),
+                min_size: 14,
+                max_size: 20,
+                authored,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Resource, Serialize, Deserialize)]
 pub struct Scenario {
     pub initial_description: String,
     pub state: ScenarioState,
     pub goals: Vec<Goal>,
+    #[serde(default)]
+    pub map: MapStyle,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ScenarioState {
     InProgress(usize),
     Success(String),
     Failure(String),
 }
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Goal {
     pub description: String,
     pub success: String,
     pub failure: String,
     pub goal_type: GoalType,
+    #[serde(default)]
+    pub layout: Option<MapLayout>,
+    #[serde(default)]
+    pub map: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum GoalType {
     ReachLocation(String),
 }
@@ -66,61 +112,152 @@ impl Default for GoalType {
     }
 }
 
-impl Goal {
-    pub fn parse(string: &str) -> Vec<Self> {
-        bevy::log::info!("Parsing goal - {string}");
-        string
-            .split('|')
-            .filter_map(|v| {
-                let v = v.trim();
-                bevy::log::info!("Parsing goal section - {v}");
-                let mut split = v.split(':');
-                let description = split.next();
-                let success = split.next();
-                let failure = split.next();
-                let goal_type = split.next();
-                if let (Some(description), Some(success), Some(failure), Some(goal_type)) =
-                    (description, success, failure, goal_type)
-                {
-                    let goal_type = match goal_type {
-                        "reach-location" => {
-                            if let Some(target_location) = split.next() {
-                                let goal = target_location.trim().to_string();
-                                Some(GoalType::ReachLocation(goal))
-                            } else {
-                                None
+// Scenario text is made of tagged sections - `{name}...{/name}`. Only braces are
+// special, so prose can use any other punctuation; write `{{` or `}}` for a literal brace.
+#[derive(Debug, Default)]
+struct Section {
+    name: String,
+    text: String,
+    children: Vec<Section>,
+}
+
+impl Section {
+    fn parse(string: &str) -> Result<Self, String> {
+        let mut stack = vec![Section::default()];
+        let mut chars = string.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    push_text(&mut stack, '{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    push_text(&mut stack, '}');
+                }
+                '{' => {
+                    let mut tag = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => {
+                                return Err(format!("Tag {{{tag} is never closed"));
                             }
+                            Some(c) => tag.push(c),
                         }
-                        _ => None,
-                    };
-                    goal_type.map(|goal_type| Goal {
-                        description: description.to_string(),
-                        success: success.to_string(),
-                        failure: failure.to_string(),
-                        goal_type,
-                    })
-                } else {
-                    None
+                    }
+                    if let Some(name) = tag.strip_prefix('/') {
+                        let section = match stack.pop() {
+                            Some(section) if !section.name.is_empty() => section,
+                            _ => return Err(format!("Found {{/{name}}} without an opening tag")),
+                        };
+                        if section.name != name {
+                            return Err(format!(
+                                "Expected {{/{}}} but found {{/{name}}}",
+                                section.name
+                            ));
+                        }
+                        if let Some(parent) = stack.last_mut() {
+                            parent.children.push(section);
+                        }
+                    } else if tag.is_empty() || tag.contains(char::is_whitespace) {
+                        return Err(format!("Invalid tag {{{tag}}}"));
+                    } else {
+                        stack.push(Section {
+                            name: tag,
+                            ..Default::default()
+                        });
+                    }
                 }
-            })
-            .collect()
+                '}' => return Err("Found an unmatched } - write }} for a literal brace".into()),
+                c => push_text(&mut stack, c),
+            }
+        }
+        match stack.pop() {
+            Some(root) if stack.is_empty() => Ok(root),
+            Some(section) => Err(format!("Section {{{}}} is never closed", section.name)),
+            None => Err("Empty scenario".into()),
+        }
+    }
+
+    fn text(&self) -> Result<String, String> {
+        if !self.children.is_empty() {
+            return Err(format!("{{{}}} can't contain other sections", self.name));
+        }
+        Ok(self.text.trim().to_string())
+    }
+
+    fn fields(&self, allowed: &[&str]) -> Result<HashMap<&str, &Section>, String> {
+        if !self.text.trim().is_empty() {
+            return Err(format!("Unexpected text \"{}\"", self.text.trim()));
+        }
+        let mut fields = HashMap::new();
+        for child in self.children.iter() {
+            if !allowed.contains(&child.name.as_str()) {
+                return Err(format!("Unknown section {{{}}}", child.name));
+            }
+            if fields.insert(child.name.as_str(), child).is_some() && child.name != "goal" {
+                return Err(format!("Section {{{}}} appears more than once", child.name));
+            }
+        }
+        Ok(fields)
+    }
+}
+
+fn push_text(stack: &mut [Section], c: char) {
+    if let Some(section) = stack.last_mut() {
+        section.text.push(c);
+    }
+}
+
+fn required(fields: &HashMap<&str, &Section>, name: &str) -> Result<String, String> {
+    match fields.get(name) {
+        Some(section) => section.text(),
+        None => Err(format!("Missing {{{name}}}")),
+    }
+}
+
+impl Goal {
+    fn from_section(section: &Section) -> Result<Self, String> {
+        let fields =
+            section.fields(&["description", "success", "failure", "reach-location", "map"])?;
+        let goal_type = GoalType::ReachLocation(required(&fields, "reach-location")?);
+        let map_field = match fields.get("map") {
+            Some(map) => Some(map.text()?).filter(|v| !v.is_empty()),
+            None => None,
+        };
+        let layout = map_field.as_deref().and_then(MapLayout::parse);
+        let map = map_field.filter(|_| layout.is_none());
+        Ok(Goal {
+            description: required(&fields, "description")?,
+            success: required(&fields, "success")?,
+            failure: required(&fields, "failure")?,
+            goal_type,
+            layout,
+            map,
+        })
     }
 }
 
 impl Scenario {
-    pub fn parse(string: &str) -> Option<Scenario> {
-        let mut split = string.split('@');
-        bevy::log::info!("Parsing Scenario: {:?}", &split);
-        if let (Some(initial_description), Some(goal_data)) = (split.next(), split.next()) {
-            let goals = Goal::parse(goal_data);
-            Some(Scenario {
-                initial_description: initial_description.trim().to_string(),
-                state: ScenarioState::InProgress(0),
-                goals,
+    pub fn parse(string: &str) -> Result<Scenario, String> {
+        let root = Section::parse(string)?;
+        let fields = root.fields(&["description", "goal"])?;
+        let goals = root
+            .children
+            .iter()
+            .filter(|section| section.name == "goal")
+            .enumerate()
+            .map(|(i, section)| {
+                Goal::from_section(section).map_err(|e| format!("Goal {} - {e}", i + 1))
             })
-        } else {
-            None
-        }
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Scenario {
+            initial_description: required(&fields, "description")?,
+            state: ScenarioState::InProgress(0),
+            goals,
+            map: MapStyle::default(),
+        })
     }
 
     pub fn succeed(&mut self) -> &ScenarioState {
@@ -163,19 +300,54 @@ impl Story {
         Self {
             phase: StoryPhase::Setup,
             scenarios: vec![],
+            generated_scenarios: 0,
             rng: rng.clone(),
             generator: StatefulStringGenerator::clone_grammar(asset),
         }
     }
 
-    fn generate_scenario(&mut self) -> Option<Scenario> {
-        let key = match self.phase {
-            StoryPhase::Setup => "intro",
-            StoryPhase::Start => "intro",
-            StoryPhase::FinalConfrontation => "confrontation",
-            StoryPhase::Complete => "complete",
+    pub fn resume(
+        rng: &mut RngComponent,
+        asset: &TraceryGrammar,
+        generated_scenarios: usize,
+        phase: StoryPhase,
+        scenarios: Vec<Scenario>,
+    ) -> Self {
+        let mut story = Self::generate(rng, asset);
+        // Only the draws matter here - the scenarios themselves are restored below
+        let _ = story.replay(generated_scenarios);
+        story.phase = phase;
+        story.scenarios = scenarios;
+        story
+    }
+
+    // Keeps the current story if the new grammar can't replay it
+    pub fn reload(&mut self, rng: &mut RngComponent, asset: &TraceryGrammar) -> Result<(), String> {
+        let mut story = Self::generate(rng, asset);
+        story.replay(self.generated_scenarios)?;
+        story.phase = self.phase;
+        story.scenarios = self.scenarios.clone();
+        *self = story;
+        Ok(())
+    }
+
+    // Plays a new story through to its last phase, so a grammar is known to work before it's used
+    pub fn validate(rng: &mut RngComponent, asset: &TraceryGrammar) -> Result<(), String> {
+        Self::generate(rng, asset).replay(STORY_LENGTH)
+    }
+
+    fn replay(&mut self, count: usize) -> Result<(), String> {
+        let mut result = Ok(());
+        for _ in 0..count {
+            if let Err(e) = self.generate_next_scenario(None) {
+                result = result.and(Err(e));
+            }
         }
-        .to_string();
+        result
+    }
+
+    fn generate_scenario(&mut self) -> Result<Scenario, String> {
+        let key = self.phase.symbol().to_string();
         bevy::log::info!("Generating Scenario with key {key}");
         let mut rng = |len| {
             if len == 0 {
@@ -189,10 +361,25 @@ impl Story {
             .generate_at(&key, &mut rng)
             .unwrap_or_default();
         bevy::log::info!("Scenario Text {text}");
-        Scenario::parse(&text)
+        match Scenario::parse(&text) {
+            Ok(mut scenario) => {
+                let layout = scenario.goals.iter().find_map(|goal| goal.layout);
+                let authored = scenario.goals.iter().find_map(|goal| goal.map.clone());
+                scenario.map = self.phase.map_style(layout, authored);
+                Ok(scenario)
+            }
+            Err(e) => {
+                let error = format!("Couldn't parse scenario for {key} - {e}");
+                bevy::log::error!("{error}");
+                Err(error)
+            }
+        }
     }
 
-    pub fn generate_next_scenario(&mut self, previous: Option<&Scenario>) -> Option<Scenario> {
+    pub fn generate_next_scenario(
+        &mut self,
+        previous: Option<&Scenario>,
+    ) -> Result<Scenario, String> {
         if let Some(previous) = previous {
             self.scenarios.push(previous.clone());
         }
@@ -208,6 +395,130 @@ impl Story {
             }
             StoryPhase::Complete => {}
         }
+        self.generated_scenarios += 1;
         self.generate_scenario()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar(yaml: &str) -> TraceryGrammar {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn parse(text: &str) -> Result<Section, String> {
+        Section::parse(text)
+    }
+
+    const BROKEN: &str = "
+starting_point: intro
+rules:
+  intro:
+  - There are no goals here
+  confrontation:
+  - Or here
+  complete:
+  - The end
+";
+
+    #[test]
+    fn reload_keeps_the_story_when_the_grammar_breaks() {
+        let rng = RngComponent::with_seed(1);
+        let good = grammar(include_str!("../assets/test_story.trace.yaml"));
+        let broken = grammar(BROKEN);
+        assert!(Story::validate(&mut rng.clone(), &good).is_ok());
+        assert!(Story::validate(&mut rng.clone(), &broken).is_err());
+
+        let mut story = Story::generate(&mut rng.clone(), &good);
+        let scenario = story.generate_next_scenario(None).unwrap();
+        story.scenarios.push(scenario.clone());
+
+        assert!(story.reload(&mut rng.clone(), &broken).is_err());
+        assert_eq!(story.generated_scenarios, 1);
+        assert_eq!(story.phase, StoryPhase::Start);
+        assert_eq!(story.scenarios.len(), 1);
+
+        assert!(story.reload(&mut rng.clone(), &good).is_ok());
+        assert_eq!(story.generated_scenarios, 1);
+        assert_eq!(
+            story.scenarios[0].initial_description,
+            scenario.initial_description
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal_text() {
+        let root = parse("{description}a {{b}} c{/description}").unwrap();
+        assert_eq!(root.children[0].text().unwrap(), "a {b} c");
+    }
+
+    #[test]
+    fn close_tags_must_match() {
+        assert_eq!(
+            parse("{goal}{success}yes{/goal}{/success}").unwrap_err(),
+            "Expected {/success} but found {/goal}"
+        );
+        assert_eq!(
+            parse("text{/goal}").unwrap_err(),
+            "Found {/goal} without an opening tag"
+        );
+        assert_eq!(
+            parse("{goal}text").unwrap_err(),
+            "Section {goal} is never closed"
+        );
+        assert!(parse("{goal text").is_err());
+        assert!(parse("a } b").is_err());
+    }
+
+    #[test]
+    fn unknown_sections_are_rejected() {
+        assert_eq!(
+            Scenario::parse("{description}a{/description}{ending}b{/ending}").unwrap_err(),
+            "Unknown section {ending}"
+        );
+    }
+
+    #[test]
+    fn fields_can_only_appear_once() {
+        assert_eq!(
+            Scenario::parse("{description}a{/description}{description}b{/description}")
+                .unwrap_err(),
+            "Section {description} appears more than once"
+        );
+    }
+
+    #[test]
+    fn text_sections_cant_nest() {
+        assert_eq!(
+            Scenario::parse("{description}a {success}b{/success}{/description}").unwrap_err(),
+            "{description} can't contain other sections"
+        );
+        assert_eq!(
+            Scenario::parse("stray {description}a{/description}").unwrap_err(),
+            "Unexpected text \"stray\""
+        );
+    }
+
+    #[test]
+    fn goals_keep_their_order() {
+        let scenario = Scenario::parse(
+            "{description}Intro{/description}
+            {goal}{description}First{/description}{success}s{/success}{failure}f{/failure}
+            {reach-location}Hill{/reach-location}{map}caves{/map}{/goal}
+            {goal}{description}Second{/description}{success}s{/success}{failure}f{/failure}
+            {reach-location}Throne{/reach-location}{map}throne-room{/map}{/goal}",
+        )
+        .unwrap();
+        assert_eq!(scenario.initial_description, "Intro");
+        assert_eq!(scenario.goals.len(), 2);
+        assert_eq!(scenario.goals[0].description, "First");
+        assert_eq!(scenario.goals[0].layout, Some(MapLayout::Caves));
+        assert_eq!(scenario.goals[1].map.as_deref(), Some("throne-room"));
+        assert_eq!(
+            Scenario::parse("{description}a{/description}{goal}{/goal}").unwrap_err(),
+            "Goal 1 - Missing {reach-location}"
+        );
+    }
+}